@@ -0,0 +1,218 @@
+// Descriptions of a scheduled plan, for scripts and dashboards.
+use std::cell::RefCell;
+
+use serde_json;
+use arena_tree;
+
+use errors::*;
+use nodes;
+use nodes::root::RootConfigData;
+use charttime::ChartTime;
+use chart;
+use schedule::{node_path, allocated_period, collect_problems};
+use plan::Plan;
+
+/// A node in the scheduled plan.  All figures are in days.
+#[derive(Serialize)]
+pub struct PlanNode {
+    pub name: String,
+    // The config file that the node is in, and its line there
+    pub file: String,
+    pub line_num: u32,
+    pub level: u32,
+    pub dev: Option<String>,
+    pub resourcing: Option<String>,
+    pub plan: f32,
+    pub done: f32,
+    pub left: f32,
+    pub gain: f32,
+    pub budget: Option<f32>,
+    // Money figures, at each dev's day rate
+    pub cost: f32,
+    pub spent: f32,
+    // Total cost, past and future, at the budget heads only
+    pub forecast: Option<f32>,
+    pub cost_budget: Option<f32>,
+    pub slack: Option<f32>,
+    pub critical: bool,
+    pub weeks: Vec<f32>,
+    pub dev_split: Vec<PlanDevSplit>,
+    pub notes: Vec<String>,
+    pub errors: Vec<String>,
+    pub children: Vec<PlanNode>,
+}
+
+/// One developer's part of a shared task, in days.
+#[derive(Serialize)]
+pub struct PlanDevSplit {
+    pub name: String,
+    pub days: f32,
+    pub weeks: Vec<f32>,
+}
+
+/// The resource remaining for a developer, in days.
+#[derive(Serialize)]
+pub struct PlanDev {
+    pub name: String,
+    pub weeks: Vec<f32>,
+    // Percentage of each week that the developer works
+    pub capacity: u32,
+    pub left: f32,
+    pub gain: f32,
+    // Cost of a day of the developer's time
+    pub rate: f32,
+    pub contractor: Option<PlanContractor>,
+}
+
+/// The work bought in from a contractor.  For contractors, the weeks and
+/// left figures on the PlanDev are the work booked rather than resource
+/// remaining.
+#[derive(Serialize)]
+pub struct PlanContractor {
+    // Most tasks the contractor works on at once, or None if unlimited
+    pub limit: Option<u32>,
+    pub booked: f32,
+    pub cost: f32,
+}
+
+#[derive(Serialize)]
+pub struct PlanLabel {
+    pub when: String,
+    pub text: String,
+}
+
+/// The whole of a scheduled plan.
+#[derive(Serialize)]
+pub struct PlanReport {
+    pub weeks: u32,
+    pub start_date: String,
+    pub now: String,
+    pub manager: Option<String>,
+    pub labels: Vec<PlanLabel>,
+    pub devs: Vec<PlanDev>,
+    pub nodes: Vec<PlanNode>,
+    // Every problem in the plan, with the line of the node at fault
+    pub problems: Vec<String>,
+}
+
+fn generate_node_report<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>,
+                                root_data: &'b RootConfigData) -> Result<PlanNode> {
+
+    let config_node = node.data.borrow();
+    let figures = chart::total_plan_figures(node, root_data);
+    if let Some(ref node_data) = config_node.node_data {
+        let mut report = node_data.generate_api_output(root_data,
+                                                       &config_node.name,
+                                                       root_data.get_file_name(config_node.file),
+                                                       config_node.line_num,
+                                                       config_node.level,
+                                                       &figures);
+        for child in node.children() {
+            report.children.push(generate_node_report(child, root_data)?);
+        }
+        return Ok(report);
+    }
+    bail!("Internal error - no node_data");
+}
+
+/// Describe the scheduled plan, with the figures and notes for every node.
+pub(crate) fn generate_plan_report<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<PlanReport> {
+
+    let root_node = root.data.borrow();
+    if let Some(ref root_data) = root_node.root_data {
+        let mut report = PlanReport {
+            weeks: root_data.get_weeks(),
+            start_date: root_data.get_start_date().to_string(),
+            now: ChartTime::from_u32(root_data.get_now()).to_string(),
+            manager: root_data.get_manager(),
+            labels: root_data.generate_label_api_output(),
+            devs: root_data.generate_dev_api_output(),
+            nodes: Vec::new(),
+            problems: collect_problems(root),
+        };
+
+        for child in root.children() {
+            report.nodes.push(generate_node_report(child, root_data)?);
+        }
+
+        return Ok(report);
+    }
+    bail!("No root data defined");
+}
+
+/// Schedule the plan in the config file, and describe it as JSON.
+pub fn get_plan_json(filename: &str) -> Result<String> {
+
+    let plan = Plan::load(filename)?;
+    let report = plan.schedule()?.report()?;
+    serde_json::to_string(&report).chain_err(|| "Failed to serialize plan")
+}
+
+#[derive(Serialize)]
+pub struct CriticalPathTask {
+    pub path: String,
+    pub file: String,
+    pub line_num: u32,
+    pub start: String,
+    pub finish: String,
+    pub slack: f32,
+}
+
+#[derive(Serialize)]
+pub struct CriticalPathReport {
+    pub project_end: Option<String>,
+
+    // The critical tasks, in the order they are completed
+    pub critical_path: Vec<CriticalPathTask>,
+
+    // All scheduled leaf tasks
+    pub tasks: Vec<CriticalPathTask>,
+}
+
+/// Describe the slack on every scheduled leaf task, and the critical path.
+pub(crate) fn generate_critical_path_report<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> CriticalPathReport {
+
+    let mut report = CriticalPathReport { project_end: None, critical_path: Vec::new(), tasks: Vec::new() };
+    let mut project_end = 0u32;
+    let mut critical: Vec<(u32, CriticalPathTask)> = Vec::new();
+    for n in root.descendants().skip(1) {
+        if let (Some((start, finish)), Some(ref node_data)) = (allocated_period(n), n.data.borrow().node_data.as_ref()) {
+            if let Some(slack) = node_data.get_slack() {
+                if finish + 1 > project_end {
+                    project_end = finish + 1;
+                }
+
+                let task = || CriticalPathTask {
+                    path: node_path(n),
+                    file: root.data.borrow().root_data.as_ref()
+                        .map_or(String::new(), |r| r.get_file_name(n.data.borrow().file).to_string()),
+                    line_num: n.data.borrow().line_num,
+                    start: ChartTime::from_u32(start).to_string(),
+                    finish: ChartTime::from_u32(finish).to_string(),
+                    slack: slack as f32 / 4.0,
+                };
+                if node_data.is_critical() {
+                    critical.push((finish, task()));
+                }
+                report.tasks.push(task());
+            }
+        }
+    }
+
+    if project_end > 0 {
+        report.project_end = Some(ChartTime::from_u32(project_end - 1).to_string());
+    }
+    critical.sort_by_key(|&(finish, _)| finish);
+    report.critical_path = critical.into_iter().map(|(_, task)| task).collect();
+
+    report
+}
+
+/// Schedule the plan in the config file, and describe its critical path
+/// as JSON.
+pub fn get_critical_path_json(filename: &str) -> Result<String> {
+
+    let plan = Plan::load(filename)?;
+    let report = plan.schedule()?.critical_path();
+    serde_json::to_string(&report).chain_err(|| "Failed to serialize critical path")
+}
@@ -0,0 +1,491 @@
+// Rows and columns of the chart, ready to be rendered by a template.
+use std::cell::RefCell;
+use std::cmp;
+
+use arena_tree;
+
+use errors::*;
+use nodes;
+use nodes::root::{RootConfigData, BorderType};
+use nodes::data::PlanFigures;
+use schedule::{node_path, collect_problems};
+
+#[derive(Serialize)]
+pub struct TemplateRow {
+    what: String,
+    what_html: String,
+    who: String,
+    // Where the node is defined, empty on the resource rows
+    line: String,
+    done: String,
+    left: String,
+    plan: String,
+    gain: String,
+    slack: String,
+    budget: String,
+    budget_gain: String,
+    cost: String,
+    // Spend to date and forecast total cost, shown at the budget heads
+    spent: String,
+    forecast: String,
+    // Describes what the figures on a resource row mean
+    resource_heading: String,
+    even: bool,
+    add_style: String,
+    notes: Vec<String>,
+    notes_html: String,
+    cells: Vec<(String, String)>,
+}
+
+impl TemplateRow {
+    pub fn new(indent: u32, line: &str, name: &str) -> TemplateRow {
+        TemplateRow {
+            // Indent is 0 on the resource rows
+            what: if indent == 0 { name.to_string() } else {
+                  format!("{}{}",
+                            &format!("{:width$}", " ", width = (indent * 3) as usize),
+                          name) },
+            what_html: String::new(),
+            who: "".to_string(),
+            done: " ".to_string(),
+            gain: " ".to_string(),
+            slack: " ".to_string(),
+            budget: " ".to_string(),
+            budget_gain: " ".to_string(),
+            cost: " ".to_string(),
+            spent: " ".to_string(),
+            forecast: " ".to_string(),
+            resource_heading: "Resource remaining".to_string(),
+            add_style: if indent == 1 { " budget".to_string() }
+                       else { String::new() },
+            line: line.to_string(),
+            left: " ".to_string(),
+            plan: " ".to_string(),
+            even: false,
+            cells: Vec::new(),
+            notes: Vec::new(),
+            notes_html: String::new(),
+        }
+    }
+
+    pub fn set_who(&mut self, who: &str) {
+        self.who = who.to_string();
+    }
+
+    fn format_f32(val: f32) -> String {
+        if val.abs() < 0.01 {
+            String::new()
+        } else {
+            format!("{:.2}", val).replace(".00", "&nbsp;&nbsp;&nbsp;").replace(".50", ".5&nbsp;")
+        }
+    }
+
+    pub fn add_cell(&mut self, root: &RootConfigData, val: f32) {
+        let mut style = TemplateContext::cell_border_style(root, 1+self.cells.len() as u32);
+
+        self.cells.push((style, TemplateRow::format_f32(val)));
+    }
+
+    pub fn add_note(&mut self, val: &str) {
+        self.notes.push(val.to_string());
+    }
+
+    pub fn set_done(&mut self, done: f32) {
+        self.done = TemplateRow::format_f32(done);
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = TemplateRow::format_f32(gain);
+    }
+
+    pub fn set_left(&mut self, left: f32) {
+        self.left = TemplateRow::format_f32(left);
+    }
+
+    pub fn set_plan(&mut self, plan: f32) {
+        self.plan = TemplateRow::format_f32(plan);
+    }
+
+    pub fn set_budget(&mut self, budget: f32) {
+        self.budget = TemplateRow::format_f32(budget);
+    }
+
+    pub fn set_budget_gain(&mut self, budget_gain: f32) {
+        self.budget_gain = TemplateRow::format_f32(budget_gain);
+    }
+
+    pub fn set_cost(&mut self, cost: f32) {
+        if cost.abs() >= 0.5 {
+            self.cost = format_money(cost);
+        }
+    }
+
+    pub fn set_spent(&mut self, spent: f32) {
+        if spent.abs() >= 0.5 {
+            self.spent = format_money(spent);
+        }
+    }
+
+    pub fn set_forecast(&mut self, forecast: f32) {
+        if forecast.abs() >= 0.5 {
+            self.forecast = format_money(forecast);
+        }
+    }
+
+    pub fn set_resource_heading(&mut self, heading: &str) {
+        self.resource_heading = heading.to_string();
+    }
+
+    pub fn set_slack(&mut self, slack: f32) {
+        self.slack = TemplateRow::format_f32(slack);
+    }
+
+    /// Highlight this row as being on the critical path
+    pub fn set_critical(&mut self) {
+        self.add_style.push_str(" critical");
+    }
+
+    fn text_headings() -> Vec<String> {
+        vec!["Line", "What", "Who", "Done", "Left", "Plan", "Gain", "Slack", "Budget", "vs Budget", "Cost", "Spent", "Forecast"]
+            .iter().map(|h| h.to_string()).collect()
+    }
+
+    /// The fixed columns of this row, in the order of the headings.
+    fn text_columns(&self) -> Vec<String> {
+        vec![self.line.clone(),
+             self.what.clone(),
+             self.who.clone(),
+             self.done.clone(),
+             self.left.clone(),
+             self.plan.clone(),
+             self.gain.clone(),
+             self.slack.clone(),
+             self.budget.clone(),
+             self.budget_gain.clone(),
+             self.cost.clone(),
+             self.spent.clone(),
+             self.forecast.clone()]
+            .iter().map(|c| c.replace("&nbsp;", " ")).collect()
+    }
+
+    fn prepare_html(&mut self) {
+
+        self.what_html = escape_html(&self.what).replace(" ", "&nbsp;");
+
+        // Notes may come straight from the config, so must be escaped.
+        // The indent of each line is kept.
+        let mut lines: Vec<String> = Vec::new();
+        for note in &self.notes {
+            for line in escape_html(note).lines() {
+                let text = line.trim_left();
+                lines.push(format!("{}{}", "&nbsp;".repeat(line.len() - text.len()), text));
+            }
+        }
+        self.notes_html = lines.join("<br>");
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct TemplateContext {
+    // Tuples of (style, content)
+    cell_headers: Vec<(String, String)>,
+
+    // Tuples of (colspan, style, content)
+    cell_labels: Vec<(u32, String, String)>,
+
+    resource_rows: Vec<TemplateRow>,
+    rows: Vec<TemplateRow>,
+
+    // URL of the plan being displayed, used to link to other views of it
+    base_url: String,
+
+    // Every problem in the plan, so they can be seen at a glance
+    problems: Vec<String>,
+}
+
+impl TemplateContext {
+
+    fn cell_border_style(root: &RootConfigData, week: u32) -> String {
+        match root.weekly_left_border(week) {
+            BorderType::None => "grid".to_string(),
+            BorderType::Start => "grid border".to_string(),
+            BorderType::Now => "grid start".to_string(),
+            BorderType::Label => "grid label".to_string(),
+        }
+    }
+
+    pub(crate) fn new(root: &RootConfigData) -> TemplateContext {
+
+        //let mut t = TemplateContext { cell_headers: Vec::new(), cell_labels: Vec::new(), rows: Vec::new(), top_height: 60, left_width: 600 };
+        let mut t = TemplateContext { ..Default::default() };
+
+        // Set up the header details
+        for s in 1..root.get_weeks() + 1 {
+            let style = TemplateContext::cell_border_style(root, s);
+            t.cell_headers.push((style, root.get_week_start_date(s).to_short_string()));
+        }
+
+        // Set up the row of labels
+        let mut colspan = 0;
+        let mut last_style: Option<String> = None;
+        let mut last_note: Option<String> = None;
+        for s in 1..root.get_weeks() + 1 {
+            let style = TemplateContext::cell_border_style(root, s);
+            colspan += 1;
+            if style != "grid" {
+                // Complete the current span
+                if let Some(style) = last_style {
+                    if let Some(note) = last_note {
+                        t.cell_labels.push((colspan, style, note));
+                    } else {
+                        t.cell_labels.push((colspan, style, "".to_string()));
+                    }
+                }
+
+                // Start on the next span
+                last_style = Some(style);
+                last_note = root.weekly_label(s);
+                colspan = 0;
+            }
+        }
+
+        // Complete the current span
+        if let Some(note) = last_note {
+            t.cell_labels.push((colspan+1, last_style.unwrap(), note));
+        } else {
+            t.cell_labels.push((colspan+1, last_style.unwrap(), "".to_string()));
+        }
+        
+        t
+    }
+
+    pub(crate) fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = base_url.to_string();
+    }
+
+    pub(crate) fn set_problems(&mut self, problems: Vec<String>) {
+        self.problems = problems;
+    }
+
+    pub(crate) fn add_resource_row(&mut self, mut row: TemplateRow) {
+        row.even = self.resource_rows.len() % 2 == 1;
+        self.resource_rows.push(row);
+    }
+
+    pub(crate) fn add_row(&mut self, mut row: TemplateRow) {
+        row.even = (self.rows.len() + self.resource_rows.len()) % 2 == 1;
+        self.rows.push(row);
+    }
+
+    /// Lay the chart out as plain text: one line per row, with any notes
+    /// on the lines that follow it.
+    pub fn generate_text(&self) -> String {
+
+        let headings = TemplateRow::text_headings();
+        let rows: Vec<(&TemplateRow, Vec<String>)> = self.resource_rows.iter()
+            .chain(self.rows.iter())
+            .map(|r| (r, r.text_columns()))
+            .collect();
+
+        let mut widths: Vec<usize> = headings.iter().map(|h| h.chars().count()).collect();
+        for &(_, ref columns) in &rows {
+            for (i, column) in columns.iter().enumerate() {
+                widths[i] = cmp::max(widths[i], column.chars().count());
+            }
+        }
+
+        // Names are left-aligned, and figures right-aligned
+        let layout = |columns: &Vec<String>| -> String {
+            let mut line = String::new();
+            for (i, column) in columns.iter().enumerate() {
+                if i == 1 || i == 2 {
+                    line.push_str(&format!("{:<width$}  ", column, width = widths[i]));
+                } else {
+                    line.push_str(&format!("{:>width$}  ", column, width = widths[i]));
+                }
+            }
+            line.push_str("|");
+            line
+        };
+
+        let mut output = String::new();
+        if self.problems.len() != 0 {
+            output.push_str("Problems:\n");
+            for problem in &self.problems {
+                output.push_str(&format!("  {}\n", problem));
+            }
+            output.push_str("\n");
+        }
+
+        output.push_str(&layout(&headings));
+        for &(_, ref week) in &self.cell_headers {
+            output.push_str(&format!("{:>6} ", week));
+        }
+        output = output.trim_right().to_string();
+        output.push_str("\n");
+
+        for (row, columns) in rows {
+            let mut line = layout(&columns);
+            for &(_, ref val) in &row.cells {
+                line.push_str(&format!("{:>7}", val.replace("&nbsp;", " ")));
+            }
+            output.push_str(line.trim_right());
+            output.push_str("\n");
+
+            for note in &row.notes {
+                for note_line in note.lines() {
+                    let line = format!("{:width$}{}", "", note_line, width = widths[0] + 2);
+                    output.push_str(line.trim_right());
+                    output.push_str("\n");
+                }
+            }
+        }
+
+        output
+    }
+
+    fn prepare_html(&mut self) {
+        for row in &mut self.resource_rows {
+            row.prepare_html();
+        }
+        for row in &mut self.rows {
+            row.prepare_html();
+        }
+    }
+}
+
+
+/// Escape text so that it displays as it is in HTML.
+pub fn escape_html(text: &str) -> String {
+    text.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+}
+
+/// Format a sum of money to the nearest whole unit.
+pub fn format_money(val: f32) -> String {
+    format!("{:.0}", val)
+}
+
+/// Total the plan figures for a node.  Leaf nodes supply their own
+/// figures; other nodes sum those of their children.
+pub fn total_plan_figures<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>,
+                                  root_data: &'b RootConfigData) -> PlanFigures {
+
+    if node.first_child().is_none() {
+        if let Some(ref node_data) = node.data.borrow().node_data {
+            return node_data.get_plan_figures(root_data);
+        }
+    }
+
+    let mut figures = PlanFigures::new();
+    for child in node.children() {
+        figures.add(&total_plan_figures(child, root_data));
+    }
+
+    figures
+}
+
+fn generate_weekly_output<'a, 'b, 'c>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, 
+                          root_data: &'c RootConfigData,
+                          mut context: &'b mut TemplateContext) -> Result<()> {
+
+    let name = node.data.borrow().name.clone();
+    let line = root_data.describe_line(node.data.borrow().file, node.data.borrow().line_num);
+    let level = node.data.borrow().level;
+    let figures = total_plan_figures(node, root_data);
+    if let Some(ref node_data) = node.data.borrow().node_data {
+        node_data.generate_weekly_output(root_data, name, &line, level, &figures, &mut context)?;
+    } else {
+        bail!("Internal error - no node_data");
+    }
+
+    for child in node.children() {
+        generate_weekly_output(child, root_data, context)?;
+    }
+
+    Ok(())
+}
+
+/// Fill in the template context for the whole chart.
+pub fn generate_chart_context<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, base_url: &str) -> Result<TemplateContext> {
+
+    let root_node = root.data.borrow();
+    if let Some(ref root_data) = root_node.root_data {
+        let mut context = TemplateContext::new(root_data);
+        context.set_base_url(base_url);
+        context.set_problems(collect_problems(root));
+        root_data.generate_dev_weekly_output(&mut context);
+
+        for child in root.children() {
+            generate_weekly_output(child, root_data, &mut context)?;
+        }
+
+        // Do any required preparation before rendering
+        context.prepare_html();
+
+        return Ok(context);
+    }
+    bail!("No root data defined");
+}
+
+/// Fill in the template context for a single developer: their resource row,
+/// then each of their tasks, then their weekly totals.
+pub fn generate_dev_chart_context<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, dev: &str, base_url: &str) -> Result<TemplateContext> {
+
+    let root_node = root.data.borrow();
+    if let Some(ref root_data) = root_node.root_data {
+        let mut context = TemplateContext::new(root_data);
+        context.set_base_url(base_url);
+        root_data.generate_single_dev_weekly_output(dev, &mut context)?;
+
+        let mut totals = vec![0u32; root_data.get_weeks() as usize];
+        for n in root.descendants().skip(1) {
+            if n.first_child().is_some() {
+                continue;
+            }
+
+            let line = root_data.describe_line(n.data.borrow().file, n.data.borrow().line_num);
+            let figures = total_plan_figures(n, root_data);
+            if let Some(ref node_data) = n.data.borrow().node_data {
+                if !node_data.has_dev(root_data, &n.data.borrow().name, dev) {
+                    continue;
+                }
+
+                for (week, val) in node_data.get_dev_weekly_numbers(dev).iter().enumerate() {
+                    totals[week] += *val;
+                }
+
+                // Collapse the path to the task into a single label
+                node_data.generate_weekly_output(root_data,
+                                                 node_path(n).replace("/", " / "),
+                                                 &line,
+                                                 0,
+                                                 &figures,
+                                                 &mut context)?;
+            }
+        }
+
+        // The total must be within what the dev has available each week
+        let available = root_data.get_dev_weekly_available(dev);
+        let mut row = TemplateRow::new(0, "", "Total");
+        let mut over_allocated: Vec<String> = Vec::new();
+        for (week, val) in totals.iter().enumerate() {
+            row.add_cell(root_data, *val as f32 / 4.0);
+            if available[week].map_or(false, |a| *val > a) {
+                over_allocated.push((week + 1).to_string());
+            }
+        }
+        if over_allocated.len() != 0 {
+            row.add_note(&format!("Over-allocated in weeks {}", over_allocated.join(", ")));
+        }
+        context.add_row(row);
+
+        // Do any required preparation before rendering
+        context.prepare_html();
+
+        return Ok(context);
+    }
+    bail!("No root data defined");
+}
@@ -42,12 +42,30 @@ impl ChartDate {
         ChartDate { dt: UTC.ymd(2001, 1, 1).and_hms(0, 0, 0) }
     }
 
+    /// Parse a date, either as d/m/yy or as yyyy-mm-dd.
     pub fn from_str(date: &str) -> Result<ChartDate> {
 
         // Avoid unnecessary recompilation of the regular expressions
         lazy_static! {
             static ref CHARTDATE_RE: Regex = 
                 Regex::new(r"^(?P<day>\d{1,2})/(?P<month>\d{1,2})?/(?P<year>\d\d)?$").unwrap();
+            static ref ISODATE_RE: Regex =
+                Regex::new(r"^(?P<year>\d{4})-(?P<month>\d{1,2})-(?P<day>\d{1,2})$").unwrap();
+        }
+
+        if let Some(c) = ISODATE_RE.captures(date) {
+            let year = c["year"].parse::<i32>()
+                .chain_err(|| format!("Cannot parse year out of: {}", date))?;
+            let month = c["month"].parse::<u32>()
+                .chain_err(|| format!("Cannot parse month out of: {}", date))?;
+            let day = c["day"].parse::<u32>()
+                .chain_err(|| format!("Cannot parse day out of: {}", date))?;
+            if let chrono::LocalResult::Single(dt) =
+                UTC.ymd_opt(year, month, day).and_hms_opt(0, 0, 0) {
+                return Ok(ChartDate { dt: dt });
+            } else {
+                bail!(format!("Cannot create date from: {}", date));
+            }
         }
 
         let c = CHARTDATE_RE.captures(date).ok_or(format!("Cannot parse ChartDate: {}", date))?;
@@ -65,6 +83,35 @@ impl ChartDate {
         }
     }
 
+    /// Whether the string looks like a yyyy-mm-dd date, rather than a
+    /// week/day/quarter.
+    pub fn is_date(desc: &str) -> bool {
+        desc.contains('-')
+    }
+
+    /// Day of the week, counting Monday as 0
+    pub fn weekday(&self) -> u32 {
+        self.dt.weekday().num_days_from_monday()
+    }
+
+    pub fn add_days(&self, days: i64) -> ChartDate {
+        ChartDate { dt: self.dt + chrono::Duration::days(days) }
+    }
+
+    /// Number of days from the other date to this one
+    pub fn days_since(&self, other: &ChartDate) -> i64 {
+        self.dt.signed_duration_since(other.dt).num_days()
+    }
+
+    /// Short form of the date, for column headers
+    pub fn to_short_string(&self) -> String {
+        format!("{}/{}", self.dt.day(), self.dt.month())
+    }
+
+    pub fn to_iso_string(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.dt.year(), self.dt.month(), self.dt.day())
+    }
+
     pub fn to_string(&self) -> String {
         format!("{}/{}/{:02}",
                 self.dt.day(),
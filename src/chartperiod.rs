@@ -3,6 +3,7 @@ use regex::Regex;
 use std;
 use std::str::FromStr;
 use charttime::ChartTime;
+use chartdate::ChartDate;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub struct ChartPeriod {
@@ -20,17 +21,23 @@ impl FromStr for ChartPeriod {
 
 impl ChartPeriod {
     pub fn from_str(period: &str) -> Result<ChartPeriod> {
+        ChartPeriod::from_str_with_dates(period, &ChartDate::new())
+    }
+
+    /// Parse a period, where either end can be given as a date.  Dates
+    /// are converted relative to the start date of the chart.
+    pub fn from_str_with_dates(period: &str, start_date: &ChartDate) -> Result<ChartPeriod> {
 
         // Avoid unnecessary recompilation of the regular expressions
         lazy_static! {
             static ref CHARTPERIOD_RE: Regex = 
-                Regex::new(r"^(?P<start>[\d/]+)\.\.(?P<end>[\d/]+)$").unwrap();
+                Regex::new(r"^(?P<start>[\d/\-]+)\.\.(?P<end>[\d/\-]+)$").unwrap();
         }
 
         let c = CHARTPERIOD_RE.captures(period).ok_or(format!("Cannot parse ChartPeriod: {}", period))?;
-        let start = c["start"].parse::<ChartTime>()
+        let start = ChartTime::from_str_with_dates(&c["start"], start_date)
             .chain_err(|| format!("Cannot parse start out of: {}", period))?;
-        let end = c["end"].parse::<ChartTime>()
+        let end = ChartTime::from_str_with_dates(&c["end"], start_date)
             .chain_err(|| format!("Cannot parse end out of: {}", period))?;
 
         let start_q = start.to_u32();
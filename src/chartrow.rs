@@ -3,7 +3,7 @@ use errors::*;
 use chartperiod::ChartPeriod;
 
 /// The time cells for a single Gantt row, split into 1/4 day chunks.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ChartRow {
 
 	num_cells: u32,
@@ -95,6 +95,10 @@ impl ChartRow {
 		}
 	}
 
+	pub fn get_num_cells(&self) -> u32 {
+		self.num_cells
+	}
+
 	/// Return a string describing the weekly numbers
 	pub fn get_weekly_summary(&self) -> String {
 
@@ -179,6 +183,15 @@ impl ChartRow {
 		Ok(())
 	}
 
+	/// Unset a range of cells
+	pub fn unset_range(&mut self, period: &ChartPeriod) -> Result<()> {
+
+		for cell in period.get_first() .. period.get_last() + 1 {
+			self.unset(cell).chain_err(|| format!("Failed to unset period {:?}", period))?;
+		}
+		Ok(())
+	}
+
 	/// Count how many of a range of cells are set
 	pub fn count_range(&self, period: &ChartPeriod) -> u32 {
 
@@ -192,6 +205,27 @@ impl ChartRow {
 		count
 	}
 
+	/// Find the cell at which a number of set cells have been seen, scanning
+	/// from the start of the range.  Returns None if the range doesn't
+	/// contain enough set cells.
+	pub fn find_fill_end(&self, count: u32, period: &ChartPeriod) -> Option<u32> {
+
+		if count == 0 {
+			return Some(period.get_first());
+		}
+		let mut seen = 0u32;
+		for cell in period.get_first() .. period.get_last() + 1 {
+			if self.is_set(cell) {
+				seen += 1;
+				if seen == count {
+					return Some(cell);
+				}
+			}
+		}
+
+		None
+	}
+
 	/// Count the number of cells that are set
 	pub fn count(&self) -> u32 {
 		let mut count = 0u32;
@@ -252,8 +286,8 @@ impl ChartRow {
         if count == 0 {
             return Ok(rc);
         }
-		let mut cell = period.get_last() - 1;
-		while cell >= period.get_first() {
+		let mut cell = period.get_last();
+		loop {
 
 	  		if self.is_set(cell) && !dest.is_set(cell) {
 	  			self.unset(cell).chain_err(|| format!("Failed transferring cells from period {:?}", period))?;
@@ -264,7 +298,11 @@ impl ChartRow {
 	  				break;
 	  			}
 	  		}
-			
+
+			// The period may start at cell 0, so stop before stepping past it
+			if cell == period.get_first() {
+				break;
+			}
 			cell -= 1;
 		}
 
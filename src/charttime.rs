@@ -3,6 +3,7 @@ use std::cmp::Ordering;
 use std::str::FromStr;
 use regex::Regex;
 use errors::*;
+use chartdate::ChartDate;
 
 #[derive(Debug, Eq, Copy, Clone)]
 pub struct ChartTime {
@@ -74,6 +75,37 @@ impl ChartTime {
            })
     }
 
+    /// Parse a time, given either as week/day/quarter or as a date.  Dates
+    /// are converted relative to the start date of the chart.
+    pub fn from_str_with_dates(desc: &str, start_date: &ChartDate) -> Result<ChartTime> {
+
+        if ChartDate::is_date(desc) {
+            let date = desc.parse::<ChartDate>()?;
+            ChartTime::from_date(&date, start_date)
+        } else {
+            ChartTime::from_str(desc)
+        }
+    }
+
+    /// Find the day in the chart that falls on the given date.  Week 1
+    /// day 1 is the start date, which is a Monday.
+    pub fn from_date(date: &ChartDate, start_date: &ChartDate) -> Result<ChartTime> {
+
+        let days = date.days_since(start_date);
+        if days < 0 {
+            bail!(format!("Date {} is before the start of the chart", date.to_iso_string()));
+        }
+        if date.weekday() > 4 {
+            bail!(format!("Date {} falls at a weekend", date.to_iso_string()));
+        }
+
+        Ok(ChartTime {
+               week: (days / 7) as u32 + 1,
+               day: Some(date.weekday() + 1),
+               quarter: None,
+           })
+    }
+
     /// Return the quarter that this time starts at
     pub fn to_u32(&self) -> u32 {
         (self.week - 1) * 20 + (self.day.unwrap_or(1) - 1) * 4 + self.quarter.unwrap_or(1) - 1
@@ -1,2 +1,112 @@
+use std::fmt;
+
 // Create the Error, ErrorKind, ResultExt, and Result types
-error_chain!{}
+error_chain!{
+    errors {
+        // A problem at a known place in the config
+        Diagnostic(d: Diagnostic) {
+            description("error in config")
+            display("{}", d.message)
+        }
+    }
+}
+
+/// A problem found in the config, with enough detail to point at the
+/// offending text.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub filename: String,
+    pub line_num: u32,
+    pub column: u32,
+    pub length: u32,
+    pub source_line: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Describe the problem on one line, prefixed by its location.
+    pub fn summary(&self) -> String {
+        format!("{}:{}:{}: {}", self.filename, self.line_num, self.column, self.message)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Show the location and source line, with a caret under the problem:
+    ///
+    /// ```text
+    ///   --> plan.txt:12:5
+    ///    |
+    /// 12 |   - budget: lots
+    ///    |     ^^^^^^^^^^^^
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line_num = self.line_num.to_string();
+        let gutter: String = line_num.chars().map(|_| ' ').collect();
+
+        // Keep any tabs in the prefix, so the caret lines up
+        let prefix: String = self.source_line.chars()
+            .take((self.column - 1) as usize)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets: String = (0..self.length).map(|_| '^').collect();
+
+        writeln!(f, "{}--> {}:{}:{}", gutter, self.filename, self.line_num, self.column)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line_num, self.source_line)?;
+        write!(f, "{} | {}{}", gutter, prefix, carets)
+    }
+}
+
+/// Find the innermost diagnostic in a chain of errors, and its position in
+/// the chain.
+fn find_diagnostic(e: &Error) -> Option<(usize, &Diagnostic)> {
+
+    let mut found = None;
+    let mut current = Some(e);
+    let mut index = 0;
+    while let Some(err) = current {
+        if let ErrorKind::Diagnostic(ref d) = *err.kind() {
+            found = Some((index, d));
+        }
+        current = err.1.next_error.as_ref().and_then(|next| next.downcast_ref::<Error>());
+        index += 1;
+    }
+
+    found
+}
+
+/// Describe an error for the user.  If the error came from a known place in
+/// the config, it is shown compiler-style: the innermost diagnostic and its
+/// causes on the first line, then the offending line of the config.
+/// Otherwise the error and its causes are given one per line.
+pub fn render_error(e: &Error) -> String {
+
+    if let Some((index, d)) = find_diagnostic(e) {
+        let mut message = d.message.clone();
+        for cause in e.iter().skip(index + 1) {
+            message = format!("{}: {}", message, cause);
+        }
+        return format!("error: {}\n{}", message, d);
+    }
+
+    let mut output = format!("error: {}", e);
+    for cause in e.iter().skip(1) {
+        output = format!("{}\ncaused by: {}", output, cause);
+    }
+    output
+}
+
+/// Describe an error on one line, for use in the notes on a node.  If the
+/// error came from a known place in the config, it starts at the innermost
+/// diagnostic, prefixed by its location.
+pub fn render_error_summary(e: &Error) -> String {
+
+    let (index, mut message) = match find_diagnostic(e) {
+        Some((index, d)) => (index, d.summary()),
+        None => (0, e.to_string())
+    };
+    for cause in e.iter().skip(index + 1) {
+        message = format!("{}: {}", message, cause);
+    }
+    message
+}
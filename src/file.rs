@@ -2,16 +2,45 @@
 // structures that can be easily iterated through.
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::mem;
+use std::fs;
 use std::fs::File;
+use std::path::{Path, PathBuf};
 use regex::Regex;
 use errors::*;
 
-// Data from a line representing a new node
+// Columns of indent for a tab.  A tab moves on to the next multiple of this.
+pub const TAB_WIDTH: u32 = 4;
+
+// Location of some text in the config, for error reporting.  Lines and
+// columns count from 1, and columns and lengths are in characters.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub file: usize,
+    pub line_num: u32,
+    pub column: u32,
+    pub length: u32,
+}
+
+// A line that doesn't add to the node tree, but is kept so that the
+// config can be written out again
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Extra {
+    Comment { file: usize, text: String },
+    Include { file: usize, path: String, comment: Option<String> },
+}
+
+// Data from a line representing a new node.  `before` holds the comments
+// and includes since the previous line, and `comment` any comment at the
+// end of the line.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct LineNode {
     pub line_num: u32,
     pub indent: u32,
     pub name: String,
+    pub span: Span,
+    pub before: Vec<Extra>,
+    pub comment: Option<String>,
 }
 
 // Data from a line representing a node attribute
@@ -19,6 +48,9 @@ pub struct LineNode {
 pub struct LineAttribute {
     pub key: String,
     pub value: String,
+    pub span: Span,
+    pub before: Vec<Extra>,
+    pub comment: Option<String>,
 }
 
 // Enum encapsulating any type of "interesting" line
@@ -29,25 +61,78 @@ pub enum Line {
 }
 
 impl Line {
-    fn new_node_line(line_num: u32, indent: u32, name: &str) -> Line {
+    fn new_node_line(line_num: u32, indent: u32, name: &str, span: Span, comment: Option<String>) -> Line {
         Line::Node(LineNode {
                        line_num: line_num,
                        indent: indent,
                        name: name.to_string(),
+                       span: span,
+                       before: Vec::new(),
+                       comment: comment,
                    })
     }
 
-    fn new_attribute_line(key: &str, value: &str) -> Line {
+    fn new_attribute_line(key: &str, value: &str, span: Span, comment: Option<String>) -> Line {
         Line::Attribute(LineAttribute {
                             key: key.to_string(),
                             value: value.to_string(),
+                            span: span,
+                            before: Vec::new(),
+                            comment: comment,
                         })
     }
 }
 
+// The text of a config file, kept for error reporting
+#[derive(Clone)]
+struct SourceFile {
+    name: String,
+    path: PathBuf,
+    lines: Vec<String>,
+
+    // Whether the file is indented with tabs, as of the last indented line
+    indent_tabs: Option<bool>,
+}
+
+// An attribute whose value is given as an indented block, still being read
+#[derive(Clone)]
+struct PendingBlock {
+    key: String,
+    indent: u32,
+    // Indent of the first line of the value, which the rest must match
+    content_indent: Option<u32>,
+    lines: Vec<String>,
+    span: Span,
+    comment: Option<String>,
+}
+
+#[derive(Clone)]
 pub struct ConfigLines {
     lines: Vec<Line>,
     pos: usize,
+    files: Vec<SourceFile>,
+
+    // Lines that could not be parsed, with the index of the node line
+    // that precedes them, if any.
+    errors: Vec<(Option<usize>, Diagnostic)>,
+
+    // Lines that parsed, but may not mean what was intended, such as
+    // those with a mix of tabs and spaces in their indent.
+    warnings: Vec<(Option<usize>, Diagnostic)>,
+
+    // After a line fails to parse, the lines that belong to it are skipped
+    // until the next node at the same indent or less.
+    skip_indent: Option<u32>,
+
+    // The files currently being read, outermost first, to catch cycles
+    // of includes.
+    including: Vec<PathBuf>,
+
+    // A block value that more lines may be added to
+    block: Option<PendingBlock>,
+
+    // Comments and includes waiting for the next line
+    pending: Vec<Extra>,
 }
 
 impl ConfigLines {
@@ -55,10 +140,24 @@ impl ConfigLines {
         ConfigLines {
             lines: Vec::new(),
             pos: 0,
+            files: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            skip_indent: None,
+            including: Vec::new(),
+            block: None,
+            pending: Vec::new(),
         }
     }
 
-    fn add_line(&mut self, line: Line) {
+    fn add_line(&mut self, mut line: Line) {
+
+        // Attach the comments and includes since the last line
+        let before = mem::replace(&mut self.pending, Vec::new());
+        match line {
+            Line::Node(ref mut node) => node.before = before,
+            Line::Attribute(ref mut attribute) => attribute.before = before,
+        }
         self.lines.push(line);
     }
 
@@ -79,52 +178,452 @@ impl ConfigLines {
         }
     }
 
+    /// Index of the next line to be read.
+    pub fn get_pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Get the attribute lines that come next.
+    pub fn peek_attributes(&self) -> Vec<LineAttribute> {
+        self.lines[self.pos..]
+            .iter()
+            .take_while(|l| match **l {
+                Line::Attribute(_) => true,
+                _ => false
+            })
+            .map(|l| match *l {
+                Line::Attribute(ref attribute) => attribute.clone(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    /// Get the comments and includes after the last line.
+    pub fn get_trailing(&self) -> Vec<Extra> {
+        self.pending.clone()
+    }
+
+    /// The names of the files read, the main file first, as used in
+    /// diagnostics.  Spans refer to files by their index in this list.
+    pub fn get_file_names(&self) -> Vec<String> {
+        self.files.iter().map(|f| f.name.clone()).collect()
+    }
+
+    /// Get the errors for every line that could not be parsed.
+    pub fn get_errors(&self) -> Vec<Diagnostic> {
+        self.errors.iter().map(|&(_, ref d)| d.clone()).collect()
+    }
+
+    /// Record a problem with a line, against the node before it.
+    fn add_error(&mut self, span: &Span, message: &str) {
+
+        let node_pos = self.last_node_pos();
+        let diagnostic = self.diagnostic(span, message);
+        self.errors.push((node_pos, diagnostic));
+    }
+
+    /// Record a doubt about a line that was parsed, against the node
+    /// before it.
+    fn add_warning(&mut self, span: &Span, message: &str) {
+
+        let node_pos = self.last_node_pos();
+        let diagnostic = self.diagnostic(span, message);
+        self.warnings.push((node_pos, diagnostic));
+    }
+
+    /// Index of the last node line read so far, if any.
+    fn last_node_pos(&self) -> Option<usize> {
+        self.lines.iter().rposition(|l| match *l {
+            Line::Node(_) => true,
+            _ => false
+        })
+    }
+
+    /// Get the errors for lines that could not be parsed, following the
+    /// node line at the given index, or before the first node if None.
+    pub fn get_errors_after(&self, node_pos: Option<usize>) -> Vec<Diagnostic> {
+        self.errors.iter()
+            .filter(|&&(pos, _)| pos == node_pos)
+            .map(|&(_, ref d)| d.clone())
+            .collect()
+    }
+
+    /// Get the warnings for every line that was parsed but may not mean
+    /// what was intended.
+    pub fn get_warnings(&self) -> Vec<Diagnostic> {
+        self.warnings.iter().map(|&(_, ref d)| d.clone()).collect()
+    }
+
+    /// Get the warnings for lines following the node line at the given
+    /// index, or before the first node if None.
+    pub fn get_warnings_after(&self, node_pos: Option<usize>) -> Vec<Diagnostic> {
+        self.warnings.iter()
+            .filter(|&&(pos, _)| pos == node_pos)
+            .map(|&(_, ref d)| d.clone())
+            .collect()
+    }
+
+    /// Describe a problem with the text at a span, so that it can be
+    /// reported alongside the offending line.
+    pub fn diagnostic(&self, span: &Span, message: &str) -> Diagnostic {
+
+        let file = &self.files[span.file];
+        Diagnostic {
+            filename: file.name.clone(),
+            line_num: span.line_num,
+            column: span.column,
+            length: span.length,
+            source_line: file.lines[(span.line_num - 1) as usize].clone(),
+            message: message.to_string(),
+        }
+    }
+
     pub fn new_from_file(filename: &str) -> Result<ConfigLines> {
 
-        let f = File::open(filename).chain_err(|| format!("Error opening {}", filename))?;
         let mut file_data = ConfigLines::new();
+        file_data.read_file(Path::new(filename), 0)?;
+        Ok(file_data)
+    }
+
+    /// Read the lines of a file, and any files that it includes.  Nodes in
+    /// the file are indented by `base_indent`, so that an included file can
+    /// be placed under the node that includes it.
+    fn read_file(&mut self, path: &Path, base_indent: u32) -> Result<()> {
+
+        let name = path.to_string_lossy().into_owned();
+        let f = File::open(path).chain_err(|| format!("Error opening {}", name))?;
+        let full_path = fs::canonicalize(path).chain_err(|| format!("Error opening {}", name))?;
+        if self.including.contains(&full_path) {
+            let mut cycle: Vec<String> = self.including
+                .iter()
+                .skip_while(|p| **p != full_path)
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+            cycle.push(full_path.to_string_lossy().into_owned());
+            bail!(format!("Include cycle: {}", cycle.join(" -> ")));
+        }
+
+        // Included files must be kept with the plan, so that a plan can't
+        // be used to read files from elsewhere.
+        if let Some(dir) = self.including.first().and_then(|p| p.parent()) {
+            if !full_path.starts_with(dir) {
+                bail!(format!("{} is outside the directory of the plan, {}", name, dir.display()));
+            }
+        }
+
+        let file = self.files.len();
+        self.files.push(SourceFile { name: name, path: path.to_path_buf(), lines: Vec::new(), indent_tabs: None });
+        self.including.push(full_path);
         let mut line_num = 0;
 
         let reader = BufReader::new(f);
         for line_rc in reader.lines() {
 
             line_num += 1;
-            let line = try!(line_rc.map_err(|e| e.to_string()));
-            file_data.process_line(&line, line_num)
-                .chain_err(|| format!("Failed reading {} at line {}", filename, line_num))?;
+            let line = match line_rc {
+                Ok(line) => line,
+                Err(e) => {
+                    self.including.pop();
+                    bail!(e.to_string());
+                }
+            };
+            self.files[file].lines.push(line.clone());
+            // Note any line that can't be parsed, and carry on with the
+            // next one.  The error is reported against the node before it.
+            if let Err(e) = self.process_line(&line, file, line_num, base_indent) {
+                let span = ConfigLines::content_span(&line, file, line_num);
+                let message: Vec<String> = e.iter().map(|cause| cause.to_string()).collect();
+                self.add_error(&span, &message.join(": "));
+            }
         }
 
-        Ok(file_data)
+        // A broken node or a block at the end of a file doesn't swallow the
+        // lines after the include.
+        self.finish_block();
+        self.including.pop();
+        self.skip_indent = None;
+        Ok(())
     }
 
-    fn process_line(&mut self, input_line: &str, line_num: u32) -> Result<()> {
+    /// Count the characters in a line up to a byte offset, giving the
+    /// column of the character at that offset.
+    fn column_at(line: &str, offset: usize) -> u32 {
+        line[..offset].chars().count() as u32 + 1
+    }
+
+    /// Count the whitespace characters at the start of a line.
+    fn leading_space(line: &str) -> usize {
+        line.chars().take_while(|c| c.is_whitespace()).count()
+    }
+
+    /// Count the columns of indent at the start of a line, with tab stops
+    /// every TAB_WIDTH columns.
+    fn indent_width(line: &str) -> u32 {
+
+        let mut width = 0;
+        for c in line.chars().take_while(|c| c.is_whitespace()) {
+            if c == '\t' {
+                width += TAB_WIDTH - width % TAB_WIDTH;
+            } else {
+                width += 1;
+            }
+        }
+
+        width
+    }
+
+    /// Check that the indent of a line doesn't mix tabs and spaces, either
+    /// within the line or with the lines before it in the file.  The line
+    /// can still be read, but may not be indented as it appears.  Only the
+    /// first line after a change from one to the other is reported.
+    fn check_indent(&mut self, line: &str, file: usize, line_num: u32) {
+
+        let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+        if indent.is_empty() {
+            return;
+        }
+
+        let tabs = indent.contains('\t');
+        let message = if tabs && indent.contains(|c| c != '\t') {
+            Some("Indent mixes tabs and spaces")
+        } else if self.files[file].indent_tabs.map_or(false, |t| t != tabs) {
+            Some(if tabs { "Indent uses tabs, but earlier lines use spaces" }
+                 else { "Indent uses spaces, but earlier lines use tabs" })
+        } else {
+            None
+        };
+
+        self.files[file].indent_tabs = Some(tabs);
+
+        if let Some(message) = message {
+            let span = Span {
+                file: file,
+                line_num: line_num,
+                column: 1,
+                length: indent.chars().count() as u32,
+            };
+            self.add_warning(&span, &format!("{}; a tab counts as {} columns", message, TAB_WIDTH));
+        }
+    }
+
+    /// Cut a line at the first '#' that isn't escaped with a backslash or
+    /// inside double quotes.
+    fn strip_comment(line: &str) -> &str {
+
+        let mut in_quotes = false;
+        let mut escaped = false;
+        for (i, c) in line.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_quotes = !in_quotes;
+            } else if c == '#' && !in_quotes {
+                return &line[..i];
+            }
+        }
+
+        line
+    }
+
+    /// Get the text of an attribute value or node name.  A value that is
+    /// all in double quotes is taken as it is, apart from backslash
+    /// escapes, so may contain '#' or look like an attribute.  Otherwise
+    /// the only escape is "\#", and any quotes are part of the text.
+    fn unquote(value: &str) -> Result<String> {
+
+        if !value.starts_with('"') {
+            return Ok(value.replace("\\#", "#"));
+        }
+
+        let mut text = String::new();
+        let mut chars = value[1..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        text.push(escaped);
+                    }
+                }
+                '"' => {
+                    if !chars.as_str().trim().is_empty() {
+                        return Ok(value.replace("\\#", "#"));
+                    }
+                    return Ok(text);
+                }
+                _ => text.push(c),
+            }
+        }
+
+        bail!("No closing quote");
+    }
+
+    /// Add a line to the block value being read, if it belongs there: that
+    /// is, if it is blank or indented at least as far as the first line of
+    /// the value, which must be indented further than the attribute.  As
+    /// in YAML, a child node can follow the block at a lesser indent.
+    fn continue_block(&mut self, line: &str) -> bool {
+
+        if let Some(ref mut block) = self.block {
+            let indent = ConfigLines::indent_width(line);
+            let in_block = line.trim().is_empty() || match block.content_indent {
+                Some(content_indent) => indent >= content_indent,
+                None => indent > block.indent,
+            };
+            if in_block {
+                if block.content_indent.is_none() && !line.trim().is_empty() {
+                    block.content_indent = Some(indent);
+                }
+                block.lines.push(line.to_string());
+                return true;
+            }
+        }
+
+        self.finish_block();
+        false
+    }
+
+    /// Add the attribute for a block value that has been read.  The indent
+    /// of the block as a whole is removed, along with trailing blank lines.
+    fn finish_block(&mut self) {
+
+        if let Some(block) = self.block.take() {
+            let mut lines: Vec<&str> = block.lines.iter().map(|l| l.trim_right()).collect();
+            while lines.last().map_or(false, |l| l.is_empty()) {
+                lines.pop();
+            }
+
+            let margin = lines.iter()
+                .filter(|l| !l.is_empty())
+                .map(|l| ConfigLines::leading_space(l))
+                .min()
+                .unwrap_or(0);
+            let text: Vec<String> = lines.iter().map(|l| l.chars().skip(margin).collect()).collect();
+            self.add_line(Line::new_attribute_line(&block.key, &text.join("\n"), block.span, block.comment));
+        }
+    }
+
+    /// Span the text of a line, ignoring leading and trailing space.
+    fn content_span(line: &str, file: usize, line_num: u32) -> Span {
+        let start = line.len() - line.trim_left().len();
+        let end = line.trim_right().len();
+        Span {
+            file: file,
+            line_num: line_num,
+            column: ConfigLines::column_at(line, start),
+            length: if end > start { line[start..end].chars().count() as u32 } else { 1 },
+        }
+    }
+
+    fn process_line(&mut self, input_line: &str, file: usize, line_num: u32, base_indent: u32) -> Result<()> {
 
         // Avoid unnecessary recompilation of the regular expressions
         lazy_static! {
-            static ref COMMENT_RE: Regex = Regex::new(r"^(?P<content>[^#]*).*$").unwrap();
             static ref BLANK_RE: Regex = Regex::new(r"^\s*$").unwrap();
-            static ref NODE_RE: Regex = Regex::new(r"^(?P<indent>\s*)(?P<name>[\w\]\[/\s,\.\-\(\)\?\&]+)$")
-                .unwrap();
+            static ref NODE_RE: Regex = Regex::new(r"^(?P<indent>\s*)(?P<name>[^\p{Cc}]*?)\s*$").unwrap();
+            static ref ATTR_LIKE_RE: Regex = Regex::new(r"^\s*\-[^:]*:").unwrap();
             static ref ATTR_RE: Regex =
                 Regex::new(r"^\s*\-\s*(?P<key>[\w\-\./]+)\s*:\s*(?P<value>.*)$").unwrap();
+            static ref INCLUDE_RE: Regex = Regex::new(r"^(?P<indent>\s*)include\s*:\s*(?P<path>.*)$").unwrap();
+        }
+
+        // The lines of a block value are taken as they are, comments and all
+        if self.continue_block(input_line) {
+            return Ok(());
         }
 
         // Strip comments, ignore blank lines.
-        let content = &COMMENT_RE.captures(input_line).unwrap()["content"];
+        let content = ConfigLines::strip_comment(input_line);
+        let comment = match input_line[content.len()..].trim() {
+            "" => None,
+            text => Some(text.to_string()),
+        };
         if BLANK_RE.is_match(content) {
+            if let Some(text) = comment {
+                self.pending.push(Extra::Comment { file: file, text: text });
+            }
             return Ok(());
         }
+        self.check_indent(content, file, line_num);
+
+        // Skip the rest of a node that failed to parse
+        if let Some(skip_indent) = self.skip_indent {
+            let indent = base_indent + ConfigLines::indent_width(content) + 1;
+            if ATTR_RE.is_match(content) || indent > skip_indent {
+                return Ok(());
+            }
+            self.skip_indent = None;
+        }
+
+        // Splice in an included file, found relative to this one.  Its
+        // nodes are indented to match the include line, so it can add
+        // siblings of the current node or, indented further, children.
+        if let Some(c) = INCLUDE_RE.captures(content) {
+            let include = c["path"].trim();
+            if include.is_empty() {
+                bail!("No file given to include");
+            }
+            let path = match self.files[file].path.parent() {
+                Some(dir) => dir.join(include),
+                None => PathBuf::from(include),
+            };
+            let indent = base_indent + ConfigLines::indent_width(content);
+            self.pending.push(Extra::Include { file: file, path: include.to_string(), comment: comment });
+            return self.read_file(&path, indent).chain_err(|| format!("Failed to include \"{}\"", include));
+        }
 
         // Try to parse as an attribute, or failing that as a node
         match ATTR_RE.captures(content) {
             Some(c) => {
-                self.add_line(Line::new_attribute_line(&c["key"], &c["value"].trim()));
+                // The span covers the key and value
+                let key = c.name("key").unwrap();
+                let value_end = key.end() + content[key.end()..].trim_right().len();
+                let span = Span {
+                    file: file,
+                    line_num: line_num,
+                    column: ConfigLines::column_at(content, key.start()),
+                    length: content[key.start()..value_end].chars().count() as u32,
+                };
+
+                // A value of "|" starts a block, made up of the indented
+                // lines that follow.
+                let value = c["value"].trim();
+                if value == "|" {
+                    self.block = Some(PendingBlock {
+                        key: c["key"].to_string(),
+                        indent: ConfigLines::indent_width(content),
+                        content_indent: None,
+                        lines: Vec::new(),
+                        span: span,
+                        comment: comment,
+                    });
+                } else {
+                    let value = ConfigLines::unquote(value)?;
+                    self.add_line(Line::new_attribute_line(&c["key"], &value, span, comment));
+                }
             }
             None => {
-                let c = NODE_RE.captures(content)
-                    .ok_or("Unable to parse line as a node or an attribute")?;
-                let indent = c["indent"].len();
-                self.add_line(Line::new_node_line(line_num, (indent + 1) as u32, &c["name"].trim()));
+                // Anything else is a node, unless it was meant to be an
+                // attribute.
+                if ATTR_LIKE_RE.is_match(content) {
+                    bail!("Unable to parse line as an attribute; if it is a node, put the name in quotes");
+                }
+
+                // If the node is broken, skip its attributes and children
+                let name = NODE_RE.captures(content)
+                    .ok_or("Unable to parse line as a node or an attribute".into())
+                    .and_then(|c| ConfigLines::unquote(&c["name"]));
+                let indent = base_indent + ConfigLines::indent_width(content);
+                let name = match name {
+                    Ok(name) => name,
+                    Err(e) => {
+                        self.skip_indent = Some(indent + 1);
+                        return Err(e);
+                    }
+                };
+                let span = ConfigLines::content_span(content, file, line_num);
+                self.add_line(Line::new_node_line(line_num, indent + 1, &name, span, comment));
             }
         };
 
@@ -0,0 +1,39 @@
+//! Scheduling for planner3 plans.
+//!
+//! Load a config file with `Plan::load`, then `Plan::schedule` it to
+//! allocate resource to every node.  The scheduled plan can be described
+//! as a report, a critical path, or the rows of the chart.  `Plan::format`
+//! writes the config back out in a standard layout.
+
+// `error_chain!` can recurse deeply
+#![recursion_limit = "1024"]
+
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate lazy_static;
+extern crate regex;
+extern crate typed_arena;
+extern crate arena_tree;
+extern crate chrono;
+
+#[macro_use]
+extern crate error_chain;
+
+pub mod errors;
+mod file;
+mod nodes;
+mod charttime;
+mod chartdate;
+mod chartperiod;
+mod chartrow;
+mod schedule;
+mod validate;
+mod chart;
+pub mod api;
+mod plan;
+mod writer;
+
+pub use plan::{Plan, ScheduledPlan};
+pub use chart::TemplateContext;
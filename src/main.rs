@@ -1,37 +1,145 @@
 #![feature(plugin)]
 #![plugin(rocket_codegen)]
 
-// `error_chain!` can recurse deeply
-#![recursion_limit = "1024"]
-
 extern crate rocket;
 extern crate rocket_contrib;
+extern crate handlebars;
 extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
 extern crate lazy_static;
 extern crate regex;
-extern crate typed_arena;
-extern crate arena_tree;
-extern crate chrono;
 
 // Import the macro. Don't forget to add `error-chain` in your
 // `Cargo.toml`!
 #[macro_use]
 extern crate error_chain;
 
-mod file;
-mod nodes;
-mod errors;
-mod charttime;
-mod chartdate;
-mod chartperiod;
-mod chartrow;
-mod web;    
+extern crate planner3;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+
+use planner3::errors;
+use planner3::errors::ResultExt;
+use planner3::Plan;
+
+mod web;
+mod render;
+
+const USAGE: &'static str = "Usage: planner3 [serve [--plan-dir <dir>]]\n       planner3 render <config> [--format html|text|json]\n       planner3 fmt <config> [--write]";
+
+/// Report a problem with the command line, and exit.
+fn usage_error(error: &str) -> ! {
+    writeln!(std::io::stderr(), "{}\n{}", error, USAGE).unwrap();
+    process::exit(1);
+}
+
+/// Render a plan to stdout, reporting any errors on stderr.
+fn render(args: &[String]) {
+
+    let mut filename: Option<&str> = None;
+    let mut format = render::OutputFormat::Html;
+    let mut options = args.iter();
+    while let Some(option) = options.next() {
+        if option == "--format" {
+            let f = options.next().unwrap_or_else(|| usage_error("--format needs a format"));
+            format = f.parse().unwrap_or_else(|e: errors::Error| usage_error(&e.to_string()));
+        } else if filename.is_none() && !option.starts_with("--") {
+            filename = Some(option);
+        } else {
+            usage_error(&format!("Unrecognised option \"{}\"", option));
+        }
+    }
+    let filename = filename.unwrap_or_else(|| usage_error("render needs a config file"));
+
+    match render::render_plan(filename, &format) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            let mut stderr = std::io::stderr();
+            writeln!(stderr, "{}", errors::render_error(&e)).unwrap();
+            process::exit(1);
+        }
+    }
+}
+
+/// Format a config file, writing the result to stdout or back to the file.
+fn format_config(filename: &str, write: bool) -> errors::Result<()> {
+
+    let plan = Plan::load(filename)?;
+    let output = plan.format()?;
+    let mut stderr = std::io::stderr();
+    for warning in plan.warnings() {
+        writeln!(stderr, "Warning: {}", warning).unwrap();
+    }
+    if write {
+        let mut f = File::create(filename).chain_err(|| format!("Error writing {}", filename))?;
+        f.write_all(output.as_bytes()).chain_err(|| format!("Error writing {}", filename))?;
+    } else {
+        print!("{}", output);
+    }
+
+    Ok(())
+}
+
+/// Write a config file out in a standard layout, reporting any errors on
+/// stderr.
+fn fmt(args: &[String]) {
+
+    let mut filename: Option<&str> = None;
+    let mut write = false;
+    for option in args {
+        if option == "--write" {
+            write = true;
+        } else if filename.is_none() && !option.starts_with("--") {
+            filename = Some(option);
+        } else {
+            usage_error(&format!("Unrecognised option \"{}\"", option));
+        }
+    }
+    let filename = filename.unwrap_or_else(|| usage_error("fmt needs a config file"));
+
+    if let Err(e) = format_config(filename, write) {
+        let mut stderr = std::io::stderr();
+        writeln!(stderr, "{}", errors::render_error(&e)).unwrap();
+        process::exit(1);
+    }
+}
 
 // Standard main function for outputting chained errors.  See
 // run() for the actual work.
 fn main() {
-    web::serve_web();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut plan_dir: Option<PathBuf> = None;
+
+    match args.get(0).map(|s| s.as_str()) {
+        None => {}
+        Some("serve") => {
+            let mut options = args.iter().skip(1);
+            while let Some(option) = options.next() {
+                if option == "--plan-dir" {
+                    let dir = options.next().unwrap_or_else(|| usage_error("--plan-dir needs a directory"));
+                    plan_dir = Some(PathBuf::from(dir));
+                } else {
+                    usage_error(&format!("Unrecognised option \"{}\"", option));
+                }
+            }
+        }
+        Some("render") => {
+            render(&args[1..]);
+            return;
+        }
+        Some("fmt") => {
+            fmt(&args[1..]);
+            return;
+        }
+        Some(command) => usage_error(&format!("Unrecognised command \"{}\"", command)),
+    }
+
+    web::serve_web(web::WebConfig::new(plan_dir));
 }
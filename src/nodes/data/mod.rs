@@ -4,13 +4,14 @@ use errors::*;
 use charttime::ChartTime;
 use chartperiod::ChartPeriod;
 use chartrow::{ChartRow, TransferResult};
-use web;
+use chart;
+use api;
 use nodes::root::RootConfigData;
 
 // Avoid unnecessary recompilation of the regular expressions
 lazy_static! {
-    static ref PLAN_RE: Regex = Regex::new(r"^(?:(?P<date>\d+(?:/\d){0,2}):)?(?P<time>\d+(?:\.\d{1,2})?)(?P<suffix>pc[ym])?$").unwrap();
-    static ref DONE_RE: Regex = Regex::new(r"^(?:(?P<date>\d+(?:/\d){0,2}):)(?P<time>\d+(?:\.\d{1,2})?)$").unwrap();
+    static ref PLAN_RE: Regex = Regex::new(r"^(?:(?P<date>\d+(?:/\d){0,2}|\d{4}-\d{1,2}-\d{1,2}):)?(?P<time>\d+(?:\.\d{1,2})?)(?P<suffix>pc[ym])?$").unwrap();
+    static ref DONE_RE: Regex = Regex::new(r"^(?:(?P<dev>\S+)\s+)?(?:(?P<date>\d+(?:/\d){0,2}|\d{4}-\d{1,2}-\d{1,2}):)(?P<time>\d+(?:\.\d{1,2})?)$").unwrap();
 }
 
 /// Strategy for scheduling child nodes
@@ -71,6 +72,20 @@ pub enum ResourcingStrategy {
     ProdSFR_part2,
 }
 
+impl ResourcingStrategy {
+    /// The name used for this strategy in the config file
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ResourcingStrategy::Management => "management",
+            ResourcingStrategy::SmearProRata => "smearprorata",
+            ResourcingStrategy::SmearRemaining => "smearremaining",
+            ResourcingStrategy::FrontLoad => "frontload",
+            ResourcingStrategy::BackLoad => "backload",
+            ResourcingStrategy::ProdSFR | ResourcingStrategy::ProdSFR_part2 => "prodsfr",
+        }
+    }
+}
+
 struct PlanEntry {
 
     // When this plan was added
@@ -97,23 +112,61 @@ struct DoneEntry {
     // then the time must be scheduled from that period.  Otherwise,
     // the time must be scheduled forward from the start time with
     // no interruptions.
-    time: u32
+    time: u32,
+
+    // Who did the work.  Required if more than one dev is on the task.
+    dev: Option<String>
 }
 
 impl DoneEntry {    
-    fn new(start: ChartTime, time: u32) -> DoneEntry {
-        DoneEntry { start, time }
+    fn new(start: ChartTime, time: u32, dev: Option<String>) -> DoneEntry {
+        DoneEntry { start: start, time: time, dev: dev }
     }
 }
 
 
+/// Plan figures for a node, in quarter days.  For nodes with children,
+/// these are accumulated from all the descendants.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PlanFigures {
+    pub plan: u32,
+    pub done: u32,
+    pub left: i32,
+
+    // Reduction in the plan since the start of the chart
+    pub gain: i32,
+
+    // Cost of the time allocated, in total and before now
+    pub cost: f32,
+    pub spent: f32,
+}
+
+impl PlanFigures {
+    pub fn new() -> PlanFigures {
+        PlanFigures { ..Default::default() }
+    }
+
+    pub fn add(&mut self, other: &PlanFigures) {
+        self.plan += other.plan;
+        self.done += other.done;
+        self.left += other.left;
+        self.gain += other.gain;
+        self.cost += other.cost;
+        self.spent += other.spent;
+    }
+}
+
 pub struct NodeConfigData {
-    // Cells are only used on leaf nodes
-    cells: ChartRow,
+    // Cells are only used on leaf nodes.  There is one row for each dev,
+    // in the same order as devs, or a single row if there are no devs.
+    cells: Vec<ChartRow>,
 
     // Budget, in quarter days
     budget: Option<u32>,
 
+    // Money budget for this node and its descendants
+    cost_budget: Option<f32>,
+
     scheduling: SchedulingStrategy,
 
     resourcing: Option<ResourcingStrategy>,
@@ -124,7 +177,18 @@ pub struct NodeConfigData {
     // Notes are problems to display on the chart
     notes: Vec<String>,
 
-    dev: Option<String>,
+    // Errors hit while scheduling this node
+    errors: Vec<String>,
+
+    // Developers working on this node
+    devs: Vec<String>,
+
+    // Percentage of the work done by each dev.  Empty for an equal split.
+    split: Vec<u32>,
+
+    // Tag of the devs that may be picked for this node, if no dev is named.
+    // "any" allows every dev.
+    pool: Option<String>,
 
     plan: Vec<PlanEntry>,
 
@@ -136,10 +200,30 @@ pub struct NodeConfigData {
 
     done: Vec<DoneEntry>,
 
+    // Window in which resource can be allocated to this node
     earliest_start: u32,
 
     latest_end: u32,
 
+    // Names of tasks that must be complete before this one can start
+    after: Vec<String>,
+
+    // Config file indices and line numbers of the nodes named in "after",
+    // once resolved
+    after_lines: Vec<(usize, u32)>,
+
+    // First cell that future resource may be allocated in, due to
+    // serial scheduling of preceding siblings or "after" dependencies.
+    sequence_start: u32,
+
+    // First and last cells allocated to this node so far, if any
+    earliest_allocated: Option<u32>,
+    latest_allocated: Option<u32>,
+
+    // Derived critical path information, in quarter days
+    slack: Option<i32>,
+    critical: bool,
+
     resource_transferred: bool
 }
 
@@ -147,11 +231,15 @@ impl NodeConfigData {
     pub fn new(num_cells: u32) -> NodeConfigData {
         NodeConfigData { 
             notes: Vec::new(), 
+            errors: Vec::new(),
             budget: None, 
+            cost_budget: None,
             scheduling: SchedulingStrategy::Parallel,
             resourcing: None,
             managed: true,
-            dev: None,
+            devs: Vec::new(),
+            split: Vec::new(),
+            pool: None,
             plan: Vec::new(),
             default_plan: Vec::new(),
             initial_plan: None,
@@ -159,36 +247,307 @@ impl NodeConfigData {
             done: Vec::new(),
             earliest_start: 0,
             latest_end: num_cells,
+            after: Vec::new(),
+            after_lines: Vec::new(),
+            sequence_start: 0,
+            earliest_allocated: None,
+            latest_allocated: None,
+            slack: None,
+            critical: false,
             resource_transferred: false,
-            cells: ChartRow::new(num_cells)
+            cells: vec![ChartRow::new(num_cells)]
         }
     }
 
+    /// The devs working on this node, as a comma-separated list.
     pub fn get_dev(&self, root_data: &RootConfigData, node_name: &str) -> Option<String> {
-        if let Some(ref d) = self.dev {
-            Some(d.clone())
-        } else if root_data.is_valid_developer(node_name) {
-            Some(node_name.to_string())
+        let devs = self.get_devs(root_data, node_name);
+        if devs.len() != 0 {
+            Some(devs.join(", "))
         } else {
             None
         }
     }
 
+    /// The devs working on this node.
+    pub fn get_devs(&self, root_data: &RootConfigData, node_name: &str) -> Vec<String> {
+        if self.devs.len() != 0 {
+            self.devs.clone()
+        } else if root_data.is_valid_developer(node_name) {
+            vec![node_name.to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Whether the given dev is one of those working on this node.
+    pub fn has_dev(&self, root_data: &RootConfigData, node_name: &str, dev: &str) -> bool {
+        self.get_dev(root_data, node_name)
+            .map_or(false, |devs| devs.split(", ").any(|d| d == dev))
+    }
+
+    /// Set the devs working on this node, given as a comma-separated list.
     pub fn set_dev(&mut self, root: &RootConfigData, dev: &String) -> Result<()> {
-        if !root.is_valid_developer(dev) {
-            bail!(format!("Developer \"{}\" not known", dev));
+        let mut devs: Vec<String> = Vec::new();
+        for d in dev.split(",").map(|d| d.trim()) {
+            if !root.is_valid_developer(d) {
+                bail!(format!("Developer \"{}\" not known", d));
+            }
+            if devs.iter().any(|x| x == d) {
+                bail!(format!("Developer \"{}\" listed twice", d));
+            }
+            devs.push(d.to_string());
+        }
+
+        let num_cells = self.cells[0].get_num_cells();
+        self.cells = devs.iter().map(|_| ChartRow::new(num_cells)).collect();
+        self.devs = devs;
+        Ok(())
+    }
+
+    pub fn get_pool(&self) -> Option<String> {
+        self.pool.clone()
+    }
+
+    pub fn set_pool(&mut self, pool: &str) -> Result<()> {
+        if pool.is_empty() || pool.contains(",") {
+            bail!(format!("Invalid pool \"{}\"", pool));
+        }
+        self.pool = Some(pool.to_string());
+        Ok(())
+    }
+
+    /// Pick a dev for a pooled node: whichever eligible dev could finish
+    /// the plan earliest with the resource they have left, allocating it
+    /// by the given strategy.  Ties, or a plan that nobody can finish, go
+    /// to the dev with the most resource left.
+    fn assign_pool_dev(&mut self, root: &RootConfigData, plan: u32, strategy: Option<ResourcingStrategy>) -> Result<()> {
+
+        let pool = match self.pool {
+            Some(ref p) => p.clone(),
+            None => return Ok(())
+        };
+        if self.done.len() != 0 {
+            bail!(format!("Done work can't be recorded against pool \"{}\"; name the dev", pool));
+        }
+
+        let quarters_in_chart = root.get_weeks() * 20;
+        let window = ChartPeriod::new(self.earliest_start, self.latest_end)
+            .chain_err(|| "latest-end is before earliest-start")?
+            .intersect(&ChartPeriod::new(root.get_now(), quarters_in_chart-1).unwrap())
+            .and_then(|p| p.limit_first(self.sequence_start));
+
+        // Only front-loaded work finishes as soon as enough cells are used;
+        // everything else is spread to the end of the window.
+        let front_loaded = match strategy {
+            None | Some(ResourcingStrategy::FrontLoad) => true,
+            _ => false
+        };
+
+        let mut best: Option<(String, Option<u32>, u32)> = None;
+        for dev in root.get_pool_devs(&pool) {
+            let (finish, free) = match window {
+                Some(ref w) => (root.get_dev_finish(&dev, plan, w, front_loaded), root.get_dev_free_cells(&dev, w)),
+                None => (None, 0)
+            };
+            let better = match best {
+                None => true,
+                Some((_, Some(best_finish), best_free)) => {
+                    finish.map_or(false, |f| f < best_finish || (f == best_finish && free > best_free))
+                },
+                Some((_, None, best_free)) => finish.is_some() || free > best_free
+            };
+            if better {
+                best = Some((dev, finish, free));
+            }
+        }
+
+        match best {
+            Some((dev, _, _)) => {
+                self.set_dev(root, &dev)?;
+                self.add_note(&format!("Assigned to {} from pool {}", dev, pool))
+            },
+            None => bail!(format!("No devs in pool \"{}\"", pool))
+        }
+    }
+
+    pub fn get_split(&self) -> &Vec<u32> {
+        &self.split
+    }
+
+    pub fn set_split_percentages(&mut self, split: &Vec<u32>) {
+        self.split = split.clone();
+    }
+
+    /// Set how the work is split between the devs, either "equal" or a
+    /// percentage for each dev such as "60, 40".
+    fn set_split(&mut self, split: &str) -> Result<()> {
+
+        self.split = Vec::new();
+        if split == "equal" {
+            return Ok(());
+        }
+
+        for part in split.split(",") {
+            let percent = part.trim().trim_right_matches("%").parse::<u32>()
+                .chain_err(|| format!("Cannot parse percentage \"{}\"", part))?;
+            self.split.push(percent);
+        }
+        if self.split.iter().fold(0, |total, p| total + p) != 100 {
+            bail!(format!("Split \"{}\" does not add up to 100%", split));
+        }
+
+        Ok(())
+    }
+
+    /// The percentage of the work done by each dev.
+    fn get_shares(&self) -> Result<Vec<u32>> {
+
+        let num_devs = self.devs.len() as u32;
+        if self.split.len() == 0 {
+            return Ok((0..num_devs).map(|i| 100 / num_devs + if i < 100 % num_devs { 1 } else { 0 }).collect());
+        }
+
+        if self.split.len() != self.devs.len() {
+            bail!(format!("Split gives {} percentages for {} devs", self.split.len(), self.devs.len()));
+        }
+        Ok(self.split.clone())
+    }
+
+    /// Divide a number of quarters between the devs according to their
+    /// shares.  Any remainder goes to the first devs.
+    fn split_quarters(shares: &Vec<u32>, quarters: u32) -> Vec<u32> {
+
+        let mut split: Vec<u32> = shares.iter().map(|s| quarters * s / 100).collect();
+        let mut remainder = quarters - split.iter().fold(0, |total, q| total + q);
+        for q in split.iter_mut() {
+            if remainder == 0 {
+                break;
+            }
+            *q += 1;
+            remainder -= 1;
+        }
+
+        split
+    }
+
+    fn count_range(&self, period: &ChartPeriod) -> u32 {
+        self.cells.iter().map(|c| c.count_range(period)).fold(0, |total, c| total + c)
+    }
+
+    fn count(&self) -> u32 {
+        self.cells.iter().map(|c| c.count()).fold(0, |total, c| total + c)
+    }
+
+    pub fn is_serial(&self) -> bool {
+        self.scheduling == SchedulingStrategy::Serial
+    }
+
+    pub fn get_weekly_numbers(&self) -> Vec<u32> {
+        let mut weeks = self.cells[0].get_weekly_numbers();
+        for row in self.cells.iter().skip(1) {
+            for (week, val) in row.get_weekly_numbers().iter().enumerate() {
+                weeks[week] += *val;
+            }
+        }
+        weeks
+    }
+
+    /// The weekly numbers for one of the devs working on this node.
+    pub fn get_dev_weekly_numbers(&self, dev: &str) -> Vec<u32> {
+        match self.devs.iter().position(|d| d == dev) {
+            Some(i) => self.cells[i].get_weekly_numbers(),
+            None => self.get_weekly_numbers(),
+        }
+    }
+
+    pub fn get_earliest_allocated(&self) -> Option<u32> {
+        self.earliest_allocated
+    }
+
+    pub fn get_latest_allocated(&self) -> Option<u32> {
+        self.latest_allocated
+    }
+
+    pub fn get_slack(&self) -> Option<i32> {
+        self.slack
+    }
+
+    pub fn set_slack(&mut self, slack: i32) {
+        self.slack = Some(slack);
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    pub fn set_critical(&mut self) {
+        self.critical = true;
+    }
+
+    pub fn set_sequence_start(&mut self, start: u32) {
+        self.sequence_start = start;
+    }
+
+    pub fn get_after(&self) -> &Vec<String> {
+        &self.after
+    }
+
+    pub fn get_after_lines(&self) -> &Vec<(usize, u32)> {
+        &self.after_lines
+    }
+
+    pub fn set_after_lines(&mut self, lines: Vec<(usize, u32)>) {
+        self.after_lines = lines;
+    }
+
+    fn set_after(&mut self, after: &str) -> Result<()> {
+
+        for name in after.split(",") {
+            let name = name.trim();
+            if name.len() == 0 {
+                bail!(format!("Failed to parse after \"{}\"", after));
+            }
+            self.after.push(name.to_string());
         }
 
-        self.dev = Some(dev.clone());
         Ok(())
     }
 
+    /// Track the first and last cells allocated to this node, so that tasks
+    /// sequenced after it can start once it is complete.
+    fn record_transfer(&mut self, result: &TransferResult) {
+        if let Some(earliest) = result.earliest {
+            if self.earliest_allocated.map_or(true, |e| earliest < e) {
+                self.earliest_allocated = Some(earliest);
+            }
+        }
+        if let Some(latest) = result.latest {
+            if self.latest_allocated.map_or(true, |l| latest > l) {
+                self.latest_allocated = Some(latest);
+            }
+        }
+    }
+
     /// Transfer resource specified in "done" from the developer to 
     /// this node's cells.
     pub fn transfer_done(&mut self, root: &mut RootConfigData, past: bool) -> Result<()> {
 
         let now = root.get_now();
-        if let Some(ref dev) = self.dev {
+        let mut results: Vec<TransferResult> = Vec::new();
+        for done in &self.done {
+            match done.dev {
+                Some(ref d) if !self.devs.contains(d) => {
+                    bail!(format!("Done work by \"{}\", who is not a dev on this task", d));
+                },
+                None if self.devs.len() > 1 => {
+                    bail!(format!("Done work at time {} must name the dev, as the task is shared", done.start.to_string()));
+                },
+                _ => {}
+            }
+        }
+
+        for (i, dev) in self.devs.iter().enumerate() {
             if let Some(dev_data) = root.get_dev_data(dev) {
                 for done in &self.done {
 
@@ -199,21 +558,34 @@ impl NodeConfigData {
                         continue;
                     }
 
+                    // Each dev's row only has the work they did
+                    if done.dev.as_ref().map_or(false, |d| d != dev) {
+                        continue;
+                    }
+                    let time = done.time;
+
                     let period = if done.time <= done.start.duration() {
                         ChartPeriod::new(done.start.to_u32(), done.start.end_as_u32()).unwrap()
                     } else {
                         ChartPeriod::new(done.start.to_u32(), done.start.to_u32()+done.time-1).unwrap()
                     };
 
-                    let result = dev_data.cells.fill_transfer_to(&mut self.cells, done.time, &period).chain_err(|| format!("Failed to add resource at time {}", done.start.to_string()))?;
+                    let before = self.cells[i].clone();
+                    let result = dev_data.cells.fill_transfer_to(&mut self.cells[i], time, &period).chain_err(|| format!("Failed to add resource at time {}", done.start.to_string()))?;
+                    dev_data.book(&before, &self.cells[i])?;
                     if result.failed != 0 {
                         // @@@ Convert time to weekly format
                         bail!(format!("Failed to add {} quarters of resource at time {}", result.failed, done.start.to_string()));
                     }
+                    results.push(result);
                 }
             }
         }
 
+        for result in &results {
+            self.record_transfer(result);
+        }
+
         Ok(())
     }
 
@@ -283,18 +655,20 @@ impl NodeConfigData {
 
         if let Some(ResourcingStrategy::Management) = self.resourcing {
 
-            if let Some(ref dev) = self.dev {
+            if let Some(dev) = self.get_dev(root, "") {
 
                 // Verify that the manager for this row matches that for the chart
                 if let Some(mgr) = root.get_manager() {
-                    if mgr != *dev {
+                    if mgr != dev {
                         bail!(format!("\"{}\" is not the configured manager, expected \"{}\"", dev, mgr));
                     }
                 } else {
                     bail!("No manager defined in global config");
                 }
 
-                root.transfer_management_resource(&mut self.cells)?;
+                let window = ChartPeriod::new(self.earliest_start, self.latest_end)
+                    .chain_err(|| "latest-end is before earliest-start")?;
+                root.transfer_management_resource(&mut self.cells[0], &window)?;
             }
 
             self.resource_transferred = true;
@@ -322,109 +696,182 @@ impl NodeConfigData {
 
         let plan = self.now_plan.unwrap();   // Total quarters we want set in the row
 
-        if let Some(ref dev) = self.dev {
-            let quarters_in_chart = root.get_weeks() * 20;
-            let chart_period = ChartPeriod::new(0, quarters_in_chart-1).unwrap();
-            let quarters_left_in_plan = if plan > self.cells.count_range(&chart_period) {
-                plan - self.cells.count_range(&chart_period)
-            } else {
-                0
-            };
-            let resource_period = root.get_dev_period(dev).unwrap_or(chart_period);
-            let remaining_period_opt = ChartPeriod::new(root.get_now(), quarters_in_chart-1).unwrap().intersect(&resource_period);
-            if remaining_period_opt.is_none() {
-                if quarters_left_in_plan == 0 {
-                    return Ok(());
-                } else {
-                    bail!(format!("Failed to write {} days because {} is not available.", quarters_left_in_plan as f32 / 4.0, dev));
+        // If no dev can be picked, later passes over the node would only
+        // fail in the same way, so give up on it.
+        if self.devs.len() == 0 {
+            let strategy = resourcing.or(self.resourcing);
+            if let Err(e) = self.assign_pool_dev(root, plan, strategy) {
+                self.resource_transferred = true;
+                return Err(e);
+            }
+        }
+
+        // Shared work is split between the devs, each drawing on their own
+        // resource.
+        let shares = self.get_shares()?;
+        let plans = NodeConfigData::split_quarters(&shares, plan);
+        let devs = self.devs.clone();
+        let mut errors: Vec<String> = Vec::new();
+        for (i, dev) in devs.iter().enumerate() {
+            if let Err(e) = self.transfer_future_dev_resource(root, resourcing, i, dev, plans[i]) {
+                if devs.len() == 1 {
+                    return Err(e);
                 }
+                errors.push(format!("{}: {}", dev, e));
             }
-            let remaining_period = remaining_period_opt.unwrap();
+        }
 
-            if let Some(dev_data) = root.get_dev_data(dev) {
+        if errors.len() != 0 {
+            bail!(errors.join("; "));
+        }
 
-                // Get allocation type
-                let mut transfer_result = TransferResult::new(quarters_left_in_plan);
-                let mut r = if resourcing.is_none() {
-                    self.resourcing
-                } else {
-                    resourcing
-                };
+        Ok(())
+    }
+
+    /// Transfer one dev's share of the plan from the developer to their
+    /// row of this node's cells.
+    fn transfer_future_dev_resource(&mut self,
+                                    root: &mut RootConfigData,
+                                    resourcing: Option<ResourcingStrategy>,
+                                    index: usize,
+                                    dev: &str,
+                                    plan: u32) -> Result<()> {
+
+        let quarters_in_chart = root.get_weeks() * 20;
+        let chart_period = ChartPeriod::new(0, quarters_in_chart-1).unwrap();
+        let quarters_left_in_plan = if plan > self.cells[index].count_range(&chart_period) {
+            plan - self.cells[index].count_range(&chart_period)
+        } else {
+            0
+        };
+        let resource_period = root.get_dev_period(dev).unwrap_or(chart_period);
+
+        // Only allocate within the earliest-start / latest-end window
+        let window = ChartPeriod::new(self.earliest_start, self.latest_end)
+            .chain_err(|| "latest-end is before earliest-start")?;
+        let resource_period_opt = resource_period.intersect(&window);
+        if resource_period_opt.is_none() {
+            if quarters_left_in_plan == 0 {
+                return Ok(());
+            } else {
+                bail!(format!("Failed to write {} days because {} is not available within {}.",
+                              quarters_left_in_plan as f32 / 4.0,
+                              dev,
+                              self.describe_window(quarters_in_chart).unwrap()));
+            }
+        }
+        let resource_period = resource_period_opt.unwrap();
+        let remaining_period_opt = ChartPeriod::new(root.get_now(), quarters_in_chart-1).unwrap().intersect(&resource_period);
+        if remaining_period_opt.is_none() {
+            if quarters_left_in_plan == 0 {
+                return Ok(());
+            } else {
+                bail!(format!("Failed to write {} days because {} is not available.", quarters_left_in_plan as f32 / 4.0, dev));
+            }
+        }
+
+        // Serial and dependent tasks can't start until the preceding tasks are complete
+        let remaining_period_opt = remaining_period_opt.unwrap().limit_first(self.sequence_start);
+        if remaining_period_opt.is_none() {
+            if quarters_left_in_plan == 0 {
+                return Ok(());
+            } else {
+                bail!(format!("Failed to write {} days because preceding tasks finish at the end of the chart.", quarters_left_in_plan as f32 / 4.0));
+            }
+        }
+        let remaining_period = remaining_period_opt.unwrap();
+
+        if let Some(dev_data) = root.get_dev_data(dev) {
+
+            // Get allocation type
+            let before = self.cells[index].clone();
+            let mut transfer_result = TransferResult::new(quarters_left_in_plan);
+            let mut r = if resourcing.is_none() {
+                self.resourcing
+            } else {
+                resourcing
+            };
 
-                match r {
-                    Some(ResourcingStrategy::Management) => {
-                        // No-op - the management row is handled out-of-band
-                        transfer_result = TransferResult::new(0);
-                    },
-                    Some(ResourcingStrategy::SmearProRata) => {
-
-                        // Time to spend per quarter day on this task
-                        let time_per_quarter = plan as f32 / (resource_period.length() as f32);
-
-                        // Time to spend in the rest of the period
-                        let mut time_to_spend = (remaining_period.length() as f32 * time_per_quarter).ceil();
-
-                        // Subtract any time already committed.
-                        time_to_spend -= self.cells
-                            .count_range(&remaining_period) as f32;
-                        if time_to_spend < -0.01 {
-                            bail!(format!("Over-committed by {} days; update plan",
-                                                   time_to_spend * -1.0));
-                        }
-
-                        // Smear the remainder.
-                        transfer_result = dev_data.cells.smear_transfer_to(&mut self.cells,
-                                                                 time_to_spend as u32,
-                                                                 &remaining_period)?;
-                        self.resource_transferred = true;
-                    },
-                    Some(ResourcingStrategy::SmearRemaining) => {
-                        transfer_result = dev_data.cells.smear_transfer_to(&mut self.cells,
+            match r {
+                Some(ResourcingStrategy::Management) => {
+                    // No-op - the management row is handled out-of-band
+                    transfer_result = TransferResult::new(0);
+                },
+                Some(ResourcingStrategy::SmearProRata) => {
+
+                    // Time to spend per quarter day on this task
+                    let time_per_quarter = plan as f32 / (resource_period.length() as f32);
+
+                    // Time to spend in the rest of the period
+                    let mut time_to_spend = (remaining_period.length() as f32 * time_per_quarter).ceil();
+
+                    // Subtract any time already committed.
+                    time_to_spend -= self.cells[index]
+                        .count_range(&remaining_period) as f32;
+                    if time_to_spend < -0.01 {
+                        bail!(format!("Over-committed by {} days; update plan",
+                                               time_to_spend * -1.0));
+                    }
+
+                    // Smear the remainder.
+                    transfer_result = dev_data.cells.smear_transfer_to(&mut self.cells[index],
+                                                                       time_to_spend as u32,
+                                                                       &remaining_period)?;
+                    self.resource_transferred = true;
+                },
+                Some(ResourcingStrategy::SmearRemaining) => {
+                    transfer_result = dev_data.cells.smear_transfer_to(&mut self.cells[index],
+                                                                       quarters_left_in_plan,
+                                                                       &remaining_period)?;
+                    self.resource_transferred = true;
+                },
+                Some(ResourcingStrategy::FrontLoad) => {
+                    transfer_result = dev_data.cells.fill_transfer_to(&mut self.cells[index],
                                                                       quarters_left_in_plan,
                                                                       &remaining_period)?;
-                        self.resource_transferred = true;
-                    },
-                    Some(ResourcingStrategy::FrontLoad) => {
-                        transfer_result = dev_data.cells.fill_transfer_to(&mut self.cells,
-                                                                     quarters_left_in_plan,
-                                                                     &remaining_period)?;
-                        self.resource_transferred = true;
-                    },
-                    Some(ResourcingStrategy::BackLoad) => {
-                        transfer_result = dev_data.cells.reverse_fill_transfer_to(&mut self.cells,
-                                                                             quarters_left_in_plan,
-                                                                             &remaining_period)?;
-                        self.resource_transferred = true;
-                    },
-                    Some(ResourcingStrategy::ProdSFR) => {
-                        // Smear 20%, then backfill 80%.  If the smear fails, add the remaining
-                        // work te the backfill.  It's unlikely to help, but we'll end up with 
-                        // an accurate result to display.
-                        let smeared_resource = quarters_left_in_plan * 20 / 100;
-
-                        transfer_result = dev_data.cells.smear_transfer_to(&mut self.cells,
-                                                                      smeared_resource,
-                                                                      &remaining_period).chain_err(|| "Failed to smear initial 20%")?;
-
-                        // Don't flag resource transferred yet until part 2 has been done
-                    }
-                    Some(ResourcingStrategy::ProdSFR_part2) => {
-                        // Backfill the remaining resource.
-                        transfer_result = dev_data.cells.reverse_fill_transfer_to(&mut self.cells,
-                                                                             quarters_left_in_plan,
-                                                                             &remaining_period).chain_err(|| "Failed to backfill 80%")?;
-                        self.resource_transferred = true;
-                    }
-                    None => {
-                        bail!("ResourcingStrategy not specified!");
-                    }
-                };
+                    self.resource_transferred = true;
+                },
+                Some(ResourcingStrategy::BackLoad) => {
+                    transfer_result = dev_data.cells.reverse_fill_transfer_to(&mut self.cells[index],
+                                                                              quarters_left_in_plan,
+                                                                              &remaining_period)?;
+                    self.resource_transferred = true;
+                },
+                Some(ResourcingStrategy::ProdSFR) => {
+                    // Smear 20%, then backfill 80%.  If the smear fails, add the remaining
+                    // work te the backfill.  It's unlikely to help, but we'll end up with 
+                    // an accurate result to display.
+                    let smeared_resource = quarters_left_in_plan * 20 / 100;
+
+                    transfer_result = dev_data.cells.smear_transfer_to(&mut self.cells[index],
+                                                                       smeared_resource,
+                                                                       &remaining_period).chain_err(|| "Failed to smear initial 20%")?;
+
+                    // Don't flag resource transferred yet until part 2 has been done
+                }
+                Some(ResourcingStrategy::ProdSFR_part2) => {
+                    // Backfill the remaining resource.
+                    transfer_result = dev_data.cells.reverse_fill_transfer_to(&mut self.cells[index],
+                                                                              quarters_left_in_plan,
+                                                                              &remaining_period).chain_err(|| "Failed to backfill 80%")?;
+                    self.resource_transferred = true;
+                }
+                None => {
+                    bail!("ResourcingStrategy not specified!");
+                }
+            };
+
+            dev_data.book(&before, &self.cells[index])?;
+            self.record_transfer(&transfer_result);
 
-                if transfer_result.failed != 0 {
-                    dev_data.unallocated += transfer_result.failed;
-                    bail!(format!("{} days unallocated", transfer_result.failed as f32 / 4.0));
+            if transfer_result.failed != 0 {
+                dev_data.unallocated += transfer_result.failed;
+                if let Some(window) = self.describe_window(quarters_in_chart) {
+                    bail!(format!("{} days unallocated; plan does not fit within {}",
+                                  transfer_result.failed as f32 / 4.0,
+                                  window));
                 }
-                // @@@ Handle the result - propagation of serialized constraints.
+                bail!(format!("{} days unallocated", transfer_result.failed as f32 / 4.0));
             }
         }
 
@@ -441,6 +888,16 @@ impl NodeConfigData {
         Ok(())
     }
 
+    fn set_cost_budget(&mut self, budget: f32) -> Result<()> {
+
+        if budget < 0.0 {
+            bail!("Cost budget must be >= 0");
+        }
+
+        self.cost_budget = Some(budget);
+        Ok(())
+    }
+
     pub fn add_note(&mut self, note: &str) -> Result<()> {
 
         self.notes.push(note.to_string());
@@ -448,6 +905,14 @@ impl NodeConfigData {
         Ok(())
     }
 
+    pub fn add_error(&mut self, error: &str) {
+        self.errors.push(error.to_string());
+    }
+
+    pub fn get_errors(&self) -> &Vec<String> {
+        &self.errors
+    }
+
     pub fn get_managed(&self) -> bool {
         self.managed
     }
@@ -469,9 +934,90 @@ impl NodeConfigData {
         Ok(())
     }
 
-    fn set_earliest_start(&mut self, when: &str) -> Result<()> {
+    pub fn get_earliest_start(&self) -> u32 {
+        self.earliest_start
+    }
+
+    pub fn get_latest_end(&self) -> u32 {
+        self.latest_end
+    }
+
+    /// Narrow the window in which this node can be resourced, for example
+    /// to apply constraints inherited from an ancestor.
+    pub fn limit_window(&mut self, earliest_start: u32, latest_end: u32) {
+        if earliest_start > self.earliest_start {
+            self.earliest_start = earliest_start;
+        }
+        if latest_end < self.latest_end {
+            self.latest_end = latest_end;
+        }
+    }
+
+    /// Check the invariants that scheduling relies on, that can be checked
+    /// on this node alone.  Returns a description of each problem found.
+    pub fn validate(&self, root: &RootConfigData, is_leaf: bool) -> Vec<String> {
+
+        let mut problems: Vec<String> = Vec::new();
+        if !is_leaf {
+            if self.done.len() != 0 {
+                problems.push("\"done\" given on a node with children; record it on the tasks".to_string());
+            }
+            if self.plan.len() != 0 {
+                problems.push("\"plan\" given on a node with children; use \"default-plan\" instead".to_string());
+            }
+        }
+
+        if self.earliest_start > self.latest_end {
+            problems.push("latest-end is before earliest-start".to_string());
+        }
+
+        for dev in &self.devs {
+            if !root.is_valid_developer(dev) {
+                problems.push(format!("Developer \"{}\" not known", dev));
+            }
+        }
+
+        // Done work must fall within the time that the dev who did it is
+        // available.  Unnamed work on a shared task is reported when it is
+        // transferred.
+        for done in &self.done {
+            let dev = match done.dev {
+                Some(ref d) => d,
+                None if self.devs.len() == 1 => &self.devs[0],
+                None => continue,
+            };
+            if let Some(dev_period) = root.get_dev_period(dev) {
+                let last = if done.time <= done.start.duration() {
+                    done.start.end_as_u32()
+                } else {
+                    done.start.to_u32() + done.time - 1
+                };
+                if done.start.to_u32() < dev_period.get_first() || last > dev_period.get_last() {
+                    problems.push(format!("done at {} is outside the time range for {}",
+                                          done.start.to_string(),
+                                          dev));
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Describe any earliest-start / latest-end constraints on this node,
+    /// for use in notes.  Returns None if the node is unconstrained.
+    fn describe_window(&self, quarters_in_chart: u32) -> Option<String> {
+        if self.earliest_start == 0 && self.latest_end + 1 >= quarters_in_chart {
+            return None;
+        }
+
+        Some(format!("{}..{}",
+                     ChartTime::from_u32(self.earliest_start).to_string(),
+                     ChartTime::from_u32(self.latest_end.min(quarters_in_chart - 1)).to_string()))
+    }
+
+    fn set_earliest_start(&mut self, root: &RootConfigData, when: &str) -> Result<()> {
 
-        let ct = when.parse::<ChartTime>().chain_err(|| format!("Failed to parse earliest-start \"{}\"", when))?;
+        let ct = root.parse_time(when).chain_err(|| format!("Failed to parse earliest-start \"{}\"", when))?;
         if ct.to_u32() > self.earliest_start {
             self.earliest_start = ct.to_u32();
         }
@@ -479,9 +1025,9 @@ impl NodeConfigData {
         Ok(())
     }
 
-    fn set_latest_end(&mut self, when: &str) -> Result<()> {
+    fn set_latest_end(&mut self, root: &RootConfigData, when: &str) -> Result<()> {
 
-        let ct = when.parse::<ChartTime>().chain_err(|| format!("Failed to parse latest-end \"{}\"", when))?;
+        let ct = root.parse_time(when).chain_err(|| format!("Failed to parse latest-end \"{}\"", when))?;
         if ct.end_as_u32() < self.latest_end {
             self.latest_end = ct.end_as_u32();
         }
@@ -489,12 +1035,12 @@ impl NodeConfigData {
         Ok(())
     }
 
-    fn new_plan_entry(&mut self, plan: &str) -> Result<PlanEntry> {
+    fn new_plan_entry(&mut self, root: &RootConfigData, plan: &str) -> Result<PlanEntry> {
 
         let c = PLAN_RE.captures(plan).ok_or(format!("Cannot parse plan part: {}", plan))?;
         let mut date = 0u32;
         if let Some(d) = c.name("date") {
-            date = ChartTime::from_str(d.as_str())
+            date = root.parse_time(d.as_str())
                                          .map(|x| x.to_u32())
                                                    .chain_err(|| format!("Failed to parse chart time \"{}\" from plan", d.as_str()))?;
         }
@@ -505,11 +1051,11 @@ impl NodeConfigData {
         Ok(PlanEntry::new(date, (time*4.0).round() as u32, suffix))   
     }
 
-    fn set_plan(&mut self, plan: &str) -> Result<()> {
+    fn set_plan(&mut self, root: &RootConfigData, plan: &str) -> Result<()> {
 
         let mut count = 0;
         for part in plan.split(", ") {
-            let p = self.new_plan_entry(part)?;
+            let p = self.new_plan_entry(root, part)?;
             self.plan.push(p);
             count += 1;
         }
@@ -521,11 +1067,11 @@ impl NodeConfigData {
         Ok(())
     }
 
-    fn set_default_plan(&mut self, plan: &str) -> Result<()> {
+    fn set_default_plan(&mut self, root: &RootConfigData, plan: &str) -> Result<()> {
 
         let mut count = 0;
         for part in plan.split(", ") {
-            let p = self.new_plan_entry(part)?;
+            let p = self.new_plan_entry(root, part)?;
             self.default_plan.push(p);
             count += 1;
         }
@@ -544,7 +1090,7 @@ impl NodeConfigData {
         Ok(())
     }
 
-    fn get_plan_internal(&self, root: &RootConfigData, dev: &Option<String>, when: u32, vec: &Vec<PlanEntry>) -> Option<u32> {
+    fn get_plan_internal(&self, root: &RootConfigData, devs: &[String], when: u32, vec: &Vec<PlanEntry>) -> Option<u32> {
 
         let mut found_val: Option<u32> = None;
         let mut found_suffix: Option<String> = None;
@@ -561,7 +1107,7 @@ impl NodeConfigData {
 
         if let Some(mut plan) = found_val {
             if let Some(ref suffix) = found_suffix {
-                let duration = root.get_plan_dev_duration(dev);
+                let duration = root.get_plan_dev_duration(devs);
                 if suffix == "pcy" {
                     plan = (plan as f32 * duration as f32 / (20.0 * 52.0)).ceil() as u32;
                 } else { // pcm
@@ -576,18 +1122,24 @@ impl NodeConfigData {
         }
     }
 
-    pub fn get_plan(&self, root: &RootConfigData, dev: &Option<String>, when: u32) -> Option<u32> {
-        self.get_plan_internal(root, dev, when, &self.plan)
+    pub fn get_plan(&self, root: &RootConfigData, devs: &[String], when: u32) -> Option<u32> {
+        self.get_plan_internal(root, devs, when, &self.plan)
     }
 
-    pub fn get_default_plan(&self, root: &RootConfigData, dev: &Option<String>, when: u32) -> Option<u32> {
-        self.get_plan_internal(root, dev, when, &self.default_plan)
+    pub fn get_default_plan(&self, root: &RootConfigData, devs: &[String], when: u32) -> Option<u32> {
+        self.get_plan_internal(root, devs, when, &self.default_plan)
     }
 
     fn add_done(&mut self, root: &RootConfigData, done: &str) -> Result<()> {
 
         let c = DONE_RE.captures(done).ok_or(format!("Cannot parse done part: \"{}\"", done))?;
-        let date = c["date"].parse::<ChartTime>().chain_err(|| format!("Failed to parse done start time \"{}\" from done", &c["date"]))?;
+        let dev = c.name("dev").map(|d| d.as_str().to_string());
+        if let Some(ref d) = dev {
+            if !root.is_valid_developer(d) {
+                bail!(format!("Developer \"{}\" not known", d));
+            }
+        }
+        let date = root.parse_time(&c["date"]).chain_err(|| format!("Failed to parse done start time \"{}\" from done", &c["date"]))?;
         let time = c["time"].parse::<f32>().chain_err(|| format!("Failed to parse done duration \"{}\" from done", &c["time"]))?;
         let time_q = (time*4.0).round() as u32;
 
@@ -599,7 +1151,7 @@ impl NodeConfigData {
             bail!(format!("Done time period \"{}\" falls outside the chart", done));
         }
 
-        self.done.push(DoneEntry::new(date, time_q));   
+        self.done.push(DoneEntry::new(date, time_q, dev));   
         Ok(())
     }
 
@@ -666,26 +1218,37 @@ impl NodeConfigData {
         if key == "budget" {
             let budget = value.parse::<f32>().chain_err(|| "Failed to parse budget")?;
             self.set_budget(budget).chain_err(|| "Failed to set budget")?;
+        } else if key == "cost-budget" {
+            let budget = value.parse::<f32>().chain_err(|| "Failed to parse cost-budget")?;
+            self.set_cost_budget(budget).chain_err(|| "Failed to set cost-budget")?;
         } else if key == "schedule" {
             self.set_schedule(value).chain_err(|| "Failed to set schedule")?;
         } else if key == "resource" {
             self.set_resource(value).chain_err(|| "Failed to set resource")?;
         } else if key == "non-managed" {
             self.set_non_managed(value).chain_err(|| "Failed to set non-managed")?;
+        } else if key == "dev" && value == "any" {
+            self.set_pool(value).chain_err(|| "Failed to set dev")?;
         } else if key == "dev" {
             self.set_dev(root, value).chain_err(|| "Failed to set dev")?;
+        } else if key == "pool" {
+            self.set_pool(value).chain_err(|| "Failed to set pool")?;
+        } else if key == "split" {
+            self.set_split(value).chain_err(|| "Failed to set split")?;
         } else if key == "note" {
             self.add_note(value).chain_err(|| "Failed to add note")?;
         } else if key == "plan" {
-            self.set_plan(value).chain_err(|| "Failed to set plan")?;
+            self.set_plan(root, value).chain_err(|| "Failed to set plan")?;
         } else if key == "default-plan" {
-            self.set_default_plan(value).chain_err(|| "Failed to set default-plan")?;
+            self.set_default_plan(root, value).chain_err(|| "Failed to set default-plan")?;
         } else if key == "done" {
             self.set_done(root, value).chain_err(|| "Failed to set done")?;
         } else if key == "earliest-start" {
-            self.set_earliest_start(value).chain_err(|| "Failed to set earliest-start")?;
+            self.set_earliest_start(root, value).chain_err(|| "Failed to set earliest-start")?;
         } else if key == "latest-end" {
-            self.set_latest_end(value).chain_err(|| "Failed to set latest-end")?;
+            self.set_latest_end(root, value).chain_err(|| "Failed to set latest-end")?;
+        } else if key == "after" {
+            self.set_after(value).chain_err(|| "Failed to set after")?;
         } else {
             bail!(format!("Unrecognised attribute \"{}\"", key));
         }
@@ -696,22 +1259,14 @@ impl NodeConfigData {
     // Work out the pro-rata plan at a given date
     pub fn pro_rata_plan_at_date(&self, when: u32, plan: u32, root: &RootConfigData) -> u32 {
 
-        // First off, get the per-cell resource allocation
-        let duration = root.get_plan_dev_duration(&self.dev);
+        // First off, get the per-cell resource allocation.  Shared work
+        // goes on whenever any of the devs is around.
+        let duration = root.get_plan_dev_duration(&self.devs);
         let work_per_cell = plan as f32 / duration as f32;
 
         // Work out work remaining
         let period = ChartPeriod::new(when, root.get_weeks() * 20 - 1).unwrap();
-        let mut cells_remaining = period.length();
-        if let Some(ref d) = self.dev {
-            if let Some(ref dp) = root.get_dev_period(d) {
-                if let Some(p) = period.intersect(dp) {
-                    cells_remaining = p.length();
-                } else {
-                    cells_remaining = 0;
-                }
-            }
-        }
+        let cells_remaining = root.count_devs_present(&self.devs, &period);
 
         let work_remaining = cells_remaining as f32 * work_per_cell;
         let work_remaining = work_remaining.ceil() as u32;
@@ -721,31 +1276,28 @@ impl NodeConfigData {
         }
 
         let time_until_now = ChartPeriod::new(0, when-1).unwrap();
-        let done = self.cells.count_range(&time_until_now);
+        let done = self.count_range(&time_until_now);
 
         done + work_remaining
     }
 
-    pub fn generate_weekly_output(&self,
-        root_data: &RootConfigData,
-        node_name: String, 
-        line_num: u32,
-        level: u32,
-        context: &mut web::TemplateContext) -> Result<()> {
-        
-        // Set up row data for self
-        let mut row = web::TemplateRow::new(level,
-                                       line_num,
-                                       &node_name);
-        for val in &self.cells.get_weekly_numbers() {
-            row.add_cell(root_data, *val as f32 / 4.0);
+    /// Work out the plan figures for this node, in isolation from any
+    /// children.
+    pub fn get_plan_figures(&self, root_data: &RootConfigData) -> PlanFigures {
+
+        let mut figures = PlanFigures::new();
+        if root_data.get_now() > 0 {
+            let time_until_now = ChartPeriod::new(0, root_data.get_now()-1).unwrap();
+            figures.done = self.count_range(&time_until_now);
+
+            for (dev, cells) in self.devs.iter().zip(self.cells.iter()) {
+                figures.spent += cells.count_range(&time_until_now) as f32 / 4.0 * root_data.get_dev_rate(dev);
+            }
         }
 
-        let time_until_now = ChartPeriod::new(0, root_data.get_now()-1).unwrap();
-        let done = self.cells.count_range(&time_until_now);
-        row.set_done(done as f32 / 4.0);
-        if let Some(dev) = self.get_dev(root_data, &node_name) {
-            row.set_who(&dev);
+        // Cost everything allocated to each dev at their day rate
+        for (dev, cells) in self.devs.iter().zip(self.cells.iter()) {
+            figures.cost += cells.count() as f32 / 4.0 * root_data.get_dev_rate(dev);
         }
 
         if let Some(p) = self.now_plan {
@@ -753,48 +1305,107 @@ impl NodeConfigData {
             if let Some(ResourcingStrategy::SmearProRata) = self.resourcing {
                 // For pro-rata resourcing, the plan value must be calculated,
                 // from the actual past, plus pro-rata-ing the future.
-
-
-                let new_plan = self.pro_rata_plan_at_date(root_data.get_now(), p, root_data);
-
-                row.set_plan(new_plan as f32 / 4.0);
+                figures.plan = self.pro_rata_plan_at_date(root_data.get_now(), p, root_data);
 
                 if let Some(old_p) = self.initial_plan {
                     let old_plan = self.pro_rata_plan_at_date(0, old_p, root_data);
-                    row.set_gain((old_plan as i32 - new_plan as i32) as f32 / 4.0);
-                }
-
-                if self.cells.count() > new_plan {
-                    row.add_note(&format!("Overspent by {}", (self.cells.count() - new_plan) as f32 / 4.0));
+                    figures.gain = old_plan as i32 - figures.plan as i32;
                 }
-
-                let left: i32 = new_plan as i32 - done as i32;
-                if left != 0 {
-                    row.set_left(left as f32 / 4.0);
-                }
-
             } else {
                 // For most resourcing strategies, the value in the plan
                 // is fixed.
-                row.set_plan(p as f32 / 4.0);
+                figures.plan = p;
 
                 if let Some(old_p) = self.initial_plan {
-                    row.set_gain((old_p as i32 - p as i32) as f32 / 4.0);
+                    figures.gain = old_p as i32 - p as i32;
                 }
+            }
 
-                if self.cells.count() > p {
-                    row.add_note(&format!("Overspent by {}", (self.cells.count() - p) as f32 / 4.0));
-                }
+            figures.left = figures.plan as i32 - figures.done as i32;
+        }
 
-                let left: i32 = p as i32 - done as i32;
-                if left != 0 {
-                    row.set_left(left as f32 / 4.0);
-                }
+        figures
+    }
+
+    /// Whether spend is tracked on this node: the top-level nodes, and any
+    /// with a cost budget.
+    fn is_budget_head(&self, level: u32) -> bool {
+        level == 1 || self.cost_budget.is_some()
+    }
+
+    /// Output the row for this node.  The figures passed in are the
+    /// totals for this node and all of its descendants.
+    pub fn generate_weekly_output(&self,
+        root_data: &RootConfigData,
+        node_name: String, 
+        line: &str,
+        level: u32,
+        figures: &PlanFigures,
+        context: &mut chart::TemplateContext) -> Result<()> {
+        
+        // Set up row data for self
+        let mut row = chart::TemplateRow::new(level,
+                                       line,
+                                       &node_name);
+        for val in &self.get_weekly_numbers() {
+            row.add_cell(root_data, *val as f32 / 4.0);
+        }
+
+        row.set_done(figures.done as f32 / 4.0);
+        if let Some(dev) = self.get_dev(root_data, &node_name) {
+            row.set_who(&dev);
+        }
+
+        row.set_plan(figures.plan as f32 / 4.0);
+        row.set_gain(figures.gain as f32 / 4.0);
+        row.set_left(figures.left as f32 / 4.0);
+        row.set_cost(figures.cost);
+
+        if self.now_plan.is_some() && self.count() > figures.plan {
+            row.add_note(&format!("Overspent by {}", (self.count() - figures.plan) as f32 / 4.0));
+        }
+
+        // Break shared work down by dev
+        if self.devs.len() > 1 && self.count() != 0 {
+            let breakdown: Vec<String> = self.devs.iter().zip(self.cells.iter())
+                .map(|(dev, cells)| format!("{} {}", dev, cells.count() as f32 / 4.0))
+                .collect();
+            row.add_note(&format!("Split {}", breakdown.join(", ")));
+        }
+
+        // Compare the plan against any budget for this node
+        if let Some(budget) = self.budget {
+            let budget_gain = budget as i32 - figures.plan as i32;
+            row.set_budget(budget as f32 / 4.0);
+            row.set_budget_gain(budget_gain as f32 / 4.0);
+            if budget_gain < 0 {
+                row.add_note(&format!("Over budget by {}", -budget_gain as f32 / 4.0));
             }
         }
 
+        // Show spend against forecast at the budget heads
+        if self.is_budget_head(level) {
+            row.set_spent(figures.spent);
+            row.set_forecast(figures.cost);
+        }
+        if let Some(cost_budget) = self.cost_budget {
+            if figures.cost > cost_budget {
+                row.add_note(&format!("Over cost budget of {} by {}",
+                                      chart::format_money(cost_budget),
+                                      chart::format_money(figures.cost - cost_budget)));
+            }
+        }
+
+        if let Some(slack) = self.slack {
+            row.set_slack(slack as f32 / 4.0);
+        }
+        if self.critical {
+            row.set_critical();
+        }
+
         for n in self.notes
-                .iter() {
+                .iter()
+                .chain(self.errors.iter()) {
             row.add_note(n);
         }
 
@@ -802,4 +1413,44 @@ impl NodeConfigData {
 
         Ok(())
     }
+
+    /// Describe this node for the JSON API.  As for the chart, the figures
+    /// passed in are the totals for this node and all of its descendants.
+    pub fn generate_api_output(&self,
+        root_data: &RootConfigData,
+        node_name: &str,
+        file: &str,
+        line_num: u32,
+        level: u32,
+        figures: &PlanFigures) -> api::PlanNode {
+
+        api::PlanNode {
+            name: node_name.to_string(),
+            file: file.to_string(),
+            line_num: line_num,
+            level: level,
+            dev: self.get_dev(root_data, node_name),
+            resourcing: self.resourcing.map(|r| r.name().to_string()),
+            plan: figures.plan as f32 / 4.0,
+            done: figures.done as f32 / 4.0,
+            left: figures.left as f32 / 4.0,
+            gain: figures.gain as f32 / 4.0,
+            budget: self.budget.map(|b| b as f32 / 4.0),
+            cost: figures.cost,
+            spent: figures.spent,
+            forecast: if self.is_budget_head(level) { Some(figures.cost) } else { None },
+            cost_budget: self.cost_budget,
+            slack: self.slack.map(|s| s as f32 / 4.0),
+            critical: self.critical,
+            weeks: self.get_weekly_numbers().iter().map(|val| *val as f32 / 4.0).collect(),
+            dev_split: self.devs.iter().zip(self.cells.iter()).map(|(dev, cells)| api::PlanDevSplit {
+                name: dev.clone(),
+                days: cells.count() as f32 / 4.0,
+                weeks: cells.get_weekly_numbers().iter().map(|val| *val as f32 / 4.0).collect(),
+            }).collect(),
+            notes: self.notes.clone(),
+            errors: self.errors.clone(),
+            children: Vec::new(),
+        }
+    }
 }
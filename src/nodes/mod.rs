@@ -14,16 +14,26 @@ use self::data::NodeConfigData;
 
 // Avoid unnecessary recompilation of the regular expressions
 lazy_static! {
-    pub static ref ROOT_NODE_RE: Regex = Regex::new(r"^\[(?P<name>(?:global)|(?:devs))\]$").unwrap();
+    pub static ref ROOT_NODE_RE: Regex = Regex::new(r"^\[(?P<name>(?:global)|(?:devs)|(?:holidays))\]$").unwrap();
 }
 
 pub struct ConfigNode {
     pub name: String,
     pub line_num: u32,
+    // Index of the config file that the node was read from; 0 is the file
+    // named on the command line
+    pub file: usize,
     pub level: u32, // Root node is level 0
 
     pub root_data: Option<RootConfigData>,
     pub node_data: Option<NodeConfigData>,
+
+    // The config for this node as written, so that it can be written out
+    // again.  The root has no line of its own, but keeps the comments and
+    // includes at the end of the file.
+    pub source: Option<file::LineNode>,
+    pub attributes: Vec<file::LineAttribute>,
+    pub trailing: Vec<file::Extra>,
 }
 
 impl ConfigNode {
@@ -31,6 +41,7 @@ impl ConfigNode {
         ConfigNode {
             name: name.to_string(),
             line_num: line_num,
+            file: 0,
             level: level,
             root_data: if is_root {
                 Some(RootConfigData::new())
@@ -42,6 +53,9 @@ impl ConfigNode {
             } else {
                 Some(NodeConfigData::new(num_cells))
             },
+            source: None,
+            attributes: Vec::new(),
+            trailing: Vec::new(),
             //attributes: HashMap::new(),
             //people: HashMap::new(),
             //cells: ChartTimeRow::new(),
@@ -52,6 +66,9 @@ impl ConfigNode {
 
     /// Generate a new node, and all children
     ///
+    /// Problems with the lines that make up the node are recorded on it,
+    /// rather than failing the whole plan.
+    ///
     /// Panics if called with !is_root, but the next line of config is
     /// not a Node.
     pub fn new_from_config<'a, 'b>
@@ -64,7 +81,6 @@ impl ConfigNode {
 
         // Create this node
         let mut node_indent = 0u32;
-        let mut node_line_num = 0u32;
         let node: &'a arena_tree::Node<'a, RefCell<ConfigNode>> = if is_root {
             arena.alloc(arena_tree::Node::new(RefCell::new(ConfigNode::new("root",
                                                                            0,
@@ -72,42 +88,60 @@ impl ConfigNode {
                                                                            is_root,
                                                                            0))))
         } else {
-            if let Some(file::Line::Node(file::LineNode { line_num, indent, name })) =
-                config.get_line() {
-                node_indent = indent;
-                node_line_num = line_num;
-                arena.alloc(arena_tree::Node::new(RefCell::new(ConfigNode::new(&name,
-                                                                               level,
-                                                                               line_num,
-                                                                               is_root,
-                                                                               20*root.unwrap().get_weeks()))))
+            if let Some(file::Line::Node(line)) = config.get_line() {
+                node_indent = line.indent;
+                let mut config_node = ConfigNode::new(&line.name,
+                                                      level,
+                                                      line.line_num,
+                                                      is_root,
+                                                      20*root.unwrap().get_weeks());
+                config_node.file = line.span.file;
+                config_node.source = Some(line);
+                arena.alloc(arena_tree::Node::new(RefCell::new(config_node)))
             } else {
                 // Should not have been called without a Node to read.
                 bail!("Internal error: new_from_config called without a node to read");
             }
         };
 
+        // Pick up any lines that couldn't be parsed, or may have been
+        // misread, up to the next node
+        let node_pos = if is_root { None } else { Some(config.get_pos() - 1) };
+        for d in config.get_errors_after(node_pos).iter().chain(&config.get_warnings_after(node_pos)) {
+            node.data.borrow_mut().add_error(&d.summary());
+        }
+
         // Add any attributes
-        while let Some(file::Line::Attribute(file::LineAttribute { key, value })) =
-            config.peek_line() {
+        while let Some(file::Line::Attribute(attribute)) = config.peek_line() {
             config.get_line();
-            node.data
+            let file::LineAttribute { ref key, ref value, ref span, .. } = attribute;
+            let result = node.data
                 .borrow_mut()
-                .add_attribute(root.unwrap(), &key, &value)
+                .add_attribute(root.unwrap(), key, value)
                 .chain_err(|| {
-                               format!("Failed to add attribute \"{}\" into node at line {}",
-                                       &key,
-                                       node_line_num)
-                           })?;
+                               ErrorKind::Diagnostic(config.diagnostic(span,
+                                                                       &format!("Failed to add attribute \"{}\"", &key)))
+                           });
+            if let Err(ref e) = result {
+                node.data.borrow_mut().add_error(&render_error_summary(e));
+            }
+            node.data.borrow_mut().attributes.push(attribute.clone());
         }
 
-        // Add any children
-        while let Some(file::Line::Node(file::LineNode { line_num, indent, name })) =
+        // Add any children.  They must all have the same indent, so a
+        // line that is dedented to somewhere between two levels is reported.
+        let mut child_indent: Option<u32> = None;
+        while let Some(file::Line::Node(file::LineNode { line_num, indent, name, span, .. })) =
             config.peek_line() {
             if indent <= node_indent {
                 break;
             }
 
+            let misaligned = child_indent.map_or(false, |i| i != indent);
+            if child_indent.is_none() {
+                child_indent = Some(indent);
+            }
+
             if is_root && ROOT_NODE_RE.is_match(&name) {
                 node.data
                     .borrow_mut()
@@ -125,6 +159,34 @@ impl ConfigNode {
                     ConfigNode::create_child(node, arena, config, root, level+1)?;                    
                 }
             }
+
+            if misaligned {
+                // The line could have been meant for a level above, so say
+                // where it was put.  The root config isn't a node, so the
+                // root takes the error.
+                let placed = if is_root {
+                    "a top-level node".to_string()
+                } else {
+                    format!("part of \"{}\"", node.data.borrow().name)
+                };
+                let message = format!("Indent doesn't match any enclosing level; read as {}, which may not be what was meant",
+                                      placed);
+                let error = config.diagnostic(&span, &message).summary();
+                match node.last_child() {
+                    Some(child) if !(is_root && ROOT_NODE_RE.is_match(&name)) => {
+                        child.data.borrow_mut().add_error(&error)
+                    }
+                    _ => node.data.borrow_mut().add_error(&error),
+                }
+            }
+        }
+
+        if is_root {
+            let mut root_node = node.data.borrow_mut();
+            root_node.trailing = config.get_trailing();
+            if let Some(ref mut root_data) = root_node.root_data {
+                root_data.set_file_names(config.get_file_names());
+            }
         }
 
         Ok(node)
@@ -143,6 +205,15 @@ arena: &'a typed_arena::Arena<arena_tree::Node<'a, RefCell<ConfigNode>>>,
         Ok(())
     }
 
+    fn add_error(&mut self, error: &str) {
+
+        if let Some(ref mut node_data) = self.node_data {
+            node_data.add_error(error);
+        } else if let Some(ref mut root_data) = self.root_data {
+            root_data.add_error(error);
+        }
+    }
+
     fn add_attribute(&mut self, root: &RootConfigData, key: &String, value: &String) -> Result<()> {
 
         if let Some(ref mut node_data) = self.node_data {
@@ -8,11 +8,22 @@ use chartdate::ChartDate;
 use chartperiod::ChartPeriod;
 use chartrow::ChartRow;
 use nodes::ROOT_NODE_RE;
-use web;
+use chart;
+use api;
 
 // Avoid unnecessary recompilation of the regular expressions
 lazy_static! {
-    static ref LABEL_RE: Regex = Regex::new(r"^(?:(?P<date>\d+(?:/\d){0,2}):\s*)(?P<text>.*)$").unwrap();
+    static ref LABEL_RE: Regex = Regex::new(r"^(?:(?P<date>\d+(?:/\d){0,2}|\d{4}-\d{1,2}-\d{1,2}):\s*)(?P<text>.*)$").unwrap();
+}
+
+/// Contractors, including "outsource", can take on several tasks at once.
+pub struct ContractorData {
+
+    // Number of tasks booked in each cell
+    bookings: Vec<u32>,
+
+    // Most tasks that can be booked in a cell, or None if unlimited
+    limit: Option<u32>,
 }
 
 pub struct DeveloperData {
@@ -20,20 +31,184 @@ pub struct DeveloperData {
     // Unallocated resource for this person
     pub cells: ChartRow,
 
+    // Cells this person can work, once capacity, leave and holidays have
+    // been taken off, but before any work is allocated
+    available: ChartRow,
+
     // Cells that we've failed to allocate for this dev
     pub unallocated: u32,
 
     // Period for which this dev is available
-    pub period: ChartPeriod
+    pub period: ChartPeriod,
+
+    // Percentage of each week that this dev is available
+    pub capacity: u32,
+
+    // Skills or teams, used to pick devs for pooled tasks
+    pub tags: Vec<String>,
+
+    // Cost of a day of this dev's time
+    pub rate: f32,
+
+    // Set if this dev is a contractor rather than staff
+    pub contractor: Option<ContractorData>,
 }
 
 impl DeveloperData {
     fn new(cells: u32, period: &ChartPeriod) -> Result<DeveloperData> {
-        let mut data = DeveloperData { cells: ChartRow::new(cells), period: *period, unallocated: 0 };
+        let mut data = DeveloperData { cells: ChartRow::new(cells), available: ChartRow::new(cells), period: *period, unallocated: 0, capacity: 100, tags: Vec::new(), rate: 0.0, contractor: None };
         data.cells.set_range(period).chain_err(|| "Developer time range not valid")?;
 
         Ok(data)
     }
+
+    /// Parse a day of the week, returning 0 for Monday.
+    fn parse_weekday(day: &str) -> Result<u32> {
+        match day.trim().to_lowercase().as_str() {
+            "mon" => Ok(0),
+            "tue" => Ok(1),
+            "wed" => Ok(2),
+            "thu" => Ok(3),
+            "fri" => Ok(4),
+            _ => bail!(format!("Cannot parse day of the week \"{}\"", day)),
+        }
+    }
+
+    /// Restrict the cells available to this dev, given either a percentage
+    /// such as "60%", or the days that they work such as "Mon-Wed, Fri".
+    /// A percentage is rounded to the nearest quarter day, and taken as
+    /// whole days spread through each week, with any part day on the first
+    /// day not worked.  The percentage is shown as given.
+    fn set_capacity(&mut self, desc: &str) -> Result<()> {
+
+        let mut available = [false; 20];
+        let capacity = if desc.ends_with("%") {
+            let percent = desc.trim_right_matches("%").trim().parse::<u32>()
+                .chain_err(|| format!("Cannot parse percentage \"{}\"", desc))?;
+            if percent > 100 {
+                bail!(format!("Capacity \"{}\" is more than 100%", desc));
+            }
+            let quarters = (percent * 20 + 50) / 100;
+            let days = quarters / 4;
+            for day in 0..5 {
+                if (day + 1) * days / 5 > day * days / 5 {
+                    for q in day * 4..(day + 1) * 4 {
+                        available[q as usize] = true;
+                    }
+                }
+            }
+            if let Some(day) = (0..5).find(|d| !available[d * 4]) {
+                for q in day * 4..day * 4 + (quarters % 4) as usize {
+                    available[q] = true;
+                }
+            }
+            percent
+        } else {
+            for part in desc.split(",") {
+                let mut days = part.split("-");
+                let first = DeveloperData::parse_weekday(days.next().unwrap())?;
+                let last = match days.next() {
+                    Some(day) => DeveloperData::parse_weekday(day)?,
+                    None => first,
+                };
+                if days.next().is_some() || last < first {
+                    bail!(format!("Cannot parse days \"{}\"", part));
+                }
+                for q in first * 4..(last + 1) * 4 {
+                    available[q as usize] = true;
+                }
+            }
+            available.iter().filter(|a| **a).count() as u32 * 5
+        };
+
+        for q in 0..self.cells.get_num_cells() {
+            if !available[(q % 20) as usize] {
+                self.cells.unset(q)?;
+            }
+        }
+        self.capacity = capacity;
+
+        Ok(())
+    }
+
+    /// Make this dev a contractor, given the number of tasks that they can
+    /// work on at once, or "unlimited".
+    fn set_contractor(&mut self, desc: &str) -> Result<()> {
+
+        let limit = if desc == "unlimited" {
+            None
+        } else {
+            let limit = desc.parse::<u32>().chain_err(|| format!("Cannot parse contractor capacity \"{}\"", desc))?;
+            if limit == 0 {
+                bail!("Contractor capacity must be at least 1");
+            }
+            Some(limit)
+        };
+
+        self.contractor = Some(ContractorData { bookings: vec![0; self.cells.get_num_cells() as usize], limit: limit });
+        Ok(())
+    }
+
+    fn set_rate(&mut self, desc: &str) -> Result<()> {
+
+        let rate = desc.parse::<f32>().chain_err(|| format!("Cannot parse day rate \"{}\"", desc))?;
+        if rate < 0.0 {
+            bail!("Day rate must not be negative");
+        }
+        self.rate = rate;
+        Ok(())
+    }
+
+    /// Record the cells that a task has taken from this dev.  Staff cells
+    /// are used up by the transfer itself, but a contractor's cells are
+    /// returned while they still have capacity for more tasks.
+    pub fn book(&mut self, before: &ChartRow, after: &ChartRow) -> Result<()> {
+
+        if let Some(ref mut contractor) = self.contractor {
+            for cell in 0..self.cells.get_num_cells() {
+                if after.is_set(cell) && !before.is_set(cell) {
+                    contractor.bookings[cell as usize] += 1;
+                    if contractor.limit.map_or(true, |l| contractor.bookings[cell as usize] < l) {
+                        self.cells.set(cell)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count the quarter days of work booked with a contractor within a
+    /// period.  Always 0 for staff.
+    pub fn count_booked(&self, period: &ChartPeriod) -> u32 {
+        match self.contractor {
+            Some(ref c) => (period.get_first()..period.get_last() + 1).map(|cell| c.bookings[cell as usize]).sum(),
+            None => 0
+        }
+    }
+
+    fn get_weekly_booked(&self) -> Vec<u32> {
+        let weeks = self.cells.get_num_cells() / 20;
+        (0..weeks).map(|week| self.count_booked(&ChartPeriod::new(week*20, (week+1)*20-1).unwrap())).collect()
+    }
+
+    /// The most quarter days of work that can be allocated to this dev in
+    /// each week, or None if there is no limit.  A contractor can work on
+    /// several tasks in each cell that they are available.
+    fn get_weekly_available(&self) -> Vec<Option<u32>> {
+        let limit = match self.contractor {
+            Some(ref c) => c.limit,
+            None => Some(1),
+        };
+        self.available.get_weekly_numbers().iter().map(|a| limit.map(|l| a * l)).collect()
+    }
+
+    fn describe_contractor(&self) -> String {
+        match self.contractor {
+            Some(ContractorData { limit: Some(l), .. }) => format!("{} at once", l),
+            _ => "unlimited".to_string()
+        }
+    }
 }
 
 struct LabelData {
@@ -43,10 +218,10 @@ struct LabelData {
 }
 
 impl LabelData {
-    fn new(defn: &str) -> Result<LabelData> {
+    fn new(defn: &str, start_date: &ChartDate) -> Result<LabelData> {
 
         let c = LABEL_RE.captures(defn).ok_or(format!("Couldn't parse label definition \"{}\"", defn))?;
-        let date = c["date"].parse::<ChartTime>().chain_err(|| format!("Failed to parse label date \"{}\"", &c["date"]))?;
+        let date = ChartTime::from_str_with_dates(&c["date"], start_date).chain_err(|| format!("Failed to parse label date \"{}\"", &c["date"]))?;
 
         Ok(LabelData{ when: date.to_u32(), text: c["text"].to_string()})
     }
@@ -70,6 +245,20 @@ pub struct RootConfigData {
     developers: HashMap<String, DeveloperData>,
 
     labels: Vec<LabelData>,
+
+    // Days off for everyone
+    holidays: Vec<ChartPeriod>,
+
+    // Problems in the root config, and lines that could not be parsed
+    // before the first node
+    errors: Vec<String>,
+
+    // The [global], [devs] and [holidays] nodes as written, so that the
+    // config can be written out again
+    sections: Vec<(file::LineNode, Vec<file::LineAttribute>)>,
+
+    // Names of the config files, the main file first
+    file_names: Vec<String>,
 }
 
 pub enum BorderType {
@@ -87,12 +276,54 @@ impl RootConfigData {
             start_date: ChartDate::new(),
             manager: None,
             labels: Vec::new(),
+            holidays: Vec::new(),
+            errors: Vec::new(),
+            sections: Vec::new(),
+            file_names: Vec::new(),
             developers: HashMap::new()
         }
     }
 
+    pub fn add_error(&mut self, error: &str) {
+        self.errors.push(error.to_string());
+    }
+
+    pub fn get_errors(&self) -> &Vec<String> {
+        &self.errors
+    }
+
+    pub fn set_file_names(&mut self, file_names: Vec<String>) {
+        self.file_names = file_names;
+    }
+
+    pub fn get_file_name(&self, file: usize) -> &str {
+        self.file_names.get(file).map_or("", |name| name.as_str())
+    }
+
+    /// Give the location of a line in the config as "file:line".
+    pub fn describe_location(&self, file: usize, line_num: u32) -> String {
+        match self.file_names.get(file) {
+            Some(name) => format!("{}:{}", name, line_num),
+            None => line_num.to_string()
+        }
+    }
+
+    /// Give a line in the config as briefly as possible: just the line
+    /// number in the main file, otherwise "file:line".
+    pub fn describe_line(&self, file: usize, line_num: u32) -> String {
+        if file == 0 {
+            line_num.to_string()
+        } else {
+            self.describe_location(file, line_num)
+        }
+    }
+
+    pub fn get_sections(&self) -> &Vec<(file::LineNode, Vec<file::LineAttribute>)> {
+        &self.sections
+    }
+
     pub fn add_label(&mut self, defn: &str) -> Result<()> {
-        let label = LabelData::new(defn)?;
+        let label = LabelData::new(defn, &self.start_date)?;
         self.labels.push(label);
         Ok(())
     }
@@ -121,9 +352,26 @@ impl RootConfigData {
 
     }
 
+    /// Set the start of the chart.  Weeks run from Monday, so any other
+    /// day is moved back to the Monday of its week.
     pub fn set_start_date(&mut self, start_date: &ChartDate) {
-        self.start_date = *start_date;
+        self.start_date = start_date.add_days(-(start_date.weekday() as i64));
+    }
 
+    /// Date of the Monday at the start of the week
+    pub fn get_week_start_date(&self, week: u32) -> ChartDate {
+        self.start_date.add_days(((week - 1) * 7) as i64)
+    }
+
+    /// Parse a time from the config, which may be given as a date.
+    pub fn parse_time(&self, desc: &str) -> Result<ChartTime> {
+        ChartTime::from_str_with_dates(desc, &self.start_date)
+    }
+
+    /// Parse a period from the config, where either end may be given as
+    /// a date.
+    pub fn parse_period(&self, desc: &str) -> Result<ChartPeriod> {
+        ChartPeriod::from_str_with_dates(desc, &self.start_date)
     }
 
     pub fn get_manager(&self) -> Option<String> {
@@ -174,30 +422,132 @@ impl RootConfigData {
         }
     }
 
-    pub fn generate_dev_weekly_output(&self, context: &mut web::TemplateContext) {
+    pub fn generate_dev_weekly_output(&self, context: &mut chart::TemplateContext) {
 
-        // Set up row data for people
-        for (dev, &DeveloperData{ref cells, ref period, unallocated}) in &self.developers {
+        // Set up row data for people.  Contractors are only shown if
+        // work has been bought in from them.
+        for (dev, data) in &self.developers {
+            if data.contractor.is_some() {
+                if data.count_booked(&ChartPeriod::new(0, self.weeks * 20 - 1).unwrap()) != 0 {
+                    self.generate_contractor_row(dev, data, context);
+                }
+            } else {
+                self.generate_dev_row(dev, data, context);
+            }
+        }
+    }
 
-            let mut row = web::TemplateRow::new(0, 0, &dev);
-            for val in &cells.get_weekly_numbers() {
-                row.add_cell(self, *val as f32 / 4.0);
+    /// Output the resource row for a single developer.
+    pub fn generate_single_dev_weekly_output(&self, name: &str, context: &mut chart::TemplateContext) -> Result<()> {
+
+        if let Some(data) = self.developers.get(name) {
+            if data.contractor.is_some() {
+                self.generate_contractor_row(name, data, context);
+            } else {
+                self.generate_dev_row(name, data, context);
             }
+        } else {
+            bail!(format!("Developer \"{}\" not known", name));
+        }
 
-            let remaining_period = ChartPeriod::new(self.get_now(), self.get_weeks() * 20 - 1).unwrap();
+        Ok(())
+    }
 
-            row.set_left(cells.count_range(&remaining_period) as f32 / 4.0);
+    fn generate_dev_row(&self, dev: &str, data: &DeveloperData, context: &mut chart::TemplateContext) {
 
-            let slack = cells.count_range(&remaining_period) as i32 - unallocated as i32;
+        let mut row = chart::TemplateRow::new(0, "", dev);
+        if data.capacity != 100 {
+            row.set_who(&format!("{}%", data.capacity));
+        }
+        for val in &data.cells.get_weekly_numbers() {
+            row.add_cell(self, *val as f32 / 4.0);
+        }
 
-            if slack != 0 {
-                row.set_gain(slack as f32 / 4.0);
-            }
+        let remaining_period = ChartPeriod::new(self.get_now(), self.get_weeks() * 20 - 1).unwrap();
+
+        row.set_left(data.cells.count_range(&remaining_period) as f32 / 4.0);
 
-            context.add_resource_row(row);
+        let slack = data.cells.count_range(&remaining_period) as i32 - data.unallocated as i32;
+
+        if slack != 0 {
+            row.set_gain(slack as f32 / 4.0);
         }
+
+        context.add_resource_row(row);
     }
 
+    /// Output the row for a contractor, showing the work bought in rather
+    /// than the resource remaining.
+    fn generate_contractor_row(&self, dev: &str, data: &DeveloperData, context: &mut chart::TemplateContext) {
+
+        let mut row = chart::TemplateRow::new(0, "", dev);
+        row.set_resource_heading("Bought in");
+        row.set_who(&data.describe_contractor());
+        for val in &data.get_weekly_booked() {
+            row.add_cell(self, *val as f32 / 4.0);
+        }
+
+        let chart_period = ChartPeriod::new(0, self.get_weeks() * 20 - 1).unwrap();
+        let remaining_period = ChartPeriod::new(self.get_now(), self.get_weeks() * 20 - 1).unwrap();
+        let booked = data.count_booked(&chart_period);
+        let left = data.count_booked(&remaining_period);
+        row.set_plan(booked as f32 / 4.0);
+        row.set_done((booked - left) as f32 / 4.0);
+        row.set_left(left as f32 / 4.0);
+        row.set_cost(booked as f32 / 4.0 * data.rate);
+        if data.rate == 0.0 {
+            row.add_note("No day rate set");
+        }
+
+        context.add_resource_row(row);
+    }
+
+    /// Describe the resource remaining for each developer, for the JSON API.
+    pub fn generate_dev_api_output(&self) -> Vec<api::PlanDev> {
+
+        let remaining_period = ChartPeriod::new(self.get_now(), self.get_weeks() * 20 - 1).unwrap();
+        let mut devs: Vec<api::PlanDev> = self.developers.iter().map(|(dev, data)| {
+            if data.contractor.is_some() {
+                let booked = data.count_booked(&ChartPeriod::new(0, self.get_weeks() * 20 - 1).unwrap());
+                return api::PlanDev {
+                    name: dev.clone(),
+                    weeks: data.get_weekly_booked().iter().map(|val| *val as f32 / 4.0).collect(),
+                    capacity: data.capacity,
+                    left: data.count_booked(&remaining_period) as f32 / 4.0,
+                    gain: 0.0,
+                    rate: data.rate,
+                    contractor: Some(api::PlanContractor {
+                        limit: data.contractor.as_ref().and_then(|c| c.limit),
+                        booked: booked as f32 / 4.0,
+                        cost: booked as f32 / 4.0 * data.rate,
+                    }),
+                };
+            }
+
+            let left = data.cells.count_range(&remaining_period);
+            api::PlanDev {
+                name: dev.clone(),
+                weeks: data.cells.get_weekly_numbers().iter().map(|val| *val as f32 / 4.0).collect(),
+                capacity: data.capacity,
+                left: left as f32 / 4.0,
+                gain: (left as i32 - data.unallocated as i32) as f32 / 4.0,
+                rate: data.rate,
+                contractor: None,
+            }
+        }).collect();
+        devs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        devs
+    }
+
+    /// Describe the labels on the chart, for the JSON API.
+    pub fn generate_label_api_output(&self) -> Vec<api::PlanLabel> {
+
+        self.labels.iter().map(|l| api::PlanLabel {
+            when: ChartTime::from_u32(l.when).to_string(),
+            text: l.text.clone(),
+        }).collect()
+    }
 
     pub fn add_developer(&mut self, name: &str, period: &ChartPeriod) -> Result<()> {
 
@@ -210,6 +560,150 @@ impl RootConfigData {
         Ok(())
     }
 
+    /// Remove a period from a developer's resource.
+    pub fn add_leave(&mut self, name: &str, period: &ChartPeriod) -> Result<()> {
+
+        if let Some(period) = self.clip_to_chart(period) {
+            if let Some(dev) = self.developers.get_mut(name) {
+                dev.cells.unset_range(&period)?;
+            } else {
+                bail!(format!("Developer \"{}\" not known", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Restrict the cells available to a developer, for part-time staff.
+    pub fn set_capacity(&mut self, name: &str, desc: &str) -> Result<()> {
+
+        if let Some(dev) = self.developers.get_mut(name) {
+            dev.set_capacity(desc)
+        } else {
+            bail!(format!("Developer \"{}\" not known", name));
+        }
+    }
+
+    /// Set the skill or team tags for a developer.
+    pub fn set_tags(&mut self, name: &str, desc: &str) -> Result<()> {
+
+        if let Some(dev) = self.developers.get_mut(name) {
+            dev.tags = desc.split(",")
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect();
+            Ok(())
+        } else {
+            bail!(format!("Developer \"{}\" not known", name));
+        }
+    }
+
+    /// Find the developers eligible for a pool, in name order.  The pool
+    /// "any" matches every developer on the staff; contractors are only
+    /// picked if tagged.
+    pub fn get_pool_devs(&self, pool: &str) -> Vec<String> {
+
+        let mut devs: Vec<String> = self.developers.iter()
+            .filter(|&(_, data)| (pool == "any" && data.contractor.is_none()) || data.tags.iter().any(|t| t == pool))
+            .map(|(name, _)| name.clone())
+            .collect();
+        devs.sort();
+        devs
+    }
+
+    /// Work out when a developer could finish a task needing the given
+    /// number of cells within the window, using their remaining resource.
+    /// Front-loaded work ends once enough cells are filled; otherwise it
+    /// runs to the dev's last free cell in the window.  Returns the last
+    /// cell used, or None if there isn't enough resource.
+    pub fn get_dev_finish(&self, name: &str, cells: u32, window: &ChartPeriod, front_loaded: bool) -> Option<u32> {
+
+        let dev = self.developers.get(name)?;
+        let period = dev.period.intersect(window)?;
+        if front_loaded {
+            dev.cells.find_fill_end(cells, &period)
+        } else if dev.cells.count_range(&period) >= cells {
+            (period.get_first()..period.get_last() + 1).rev().find(|&cell| dev.cells.is_set(cell))
+        } else {
+            None
+        }
+    }
+
+    /// Count the resource a developer has left within the window.
+    pub fn get_dev_free_cells(&self, name: &str, window: &ChartPeriod) -> u32 {
+
+        match self.developers.get(name) {
+            Some(dev) => dev.period.intersect(window).map_or(0, |p| dev.cells.count_range(&p)),
+            None => 0
+        }
+    }
+
+    /// Make a developer a contractor, who can work on several tasks at once.
+    pub fn set_contractor(&mut self, name: &str, desc: &str) -> Result<()> {
+
+        if let Some(dev) = self.developers.get_mut(name) {
+            dev.set_contractor(desc)
+        } else {
+            bail!(format!("Developer \"{}\" not known", name));
+        }
+    }
+
+    pub fn set_rate(&mut self, name: &str, desc: &str) -> Result<()> {
+
+        if let Some(dev) = self.developers.get_mut(name) {
+            dev.set_rate(desc)
+        } else {
+            bail!(format!("Developer \"{}\" not known", name));
+        }
+    }
+
+    /// Record a period off for everyone.  It is taken from their resource
+    /// by apply_holidays, once the whole config has been read.
+    pub fn add_holiday(&mut self, period: &ChartPeriod) {
+        self.holidays.push(*period);
+    }
+
+    /// Remove the holidays from the resource of all staff.  Contractors,
+    /// including outsource, work through them.  What is left is everyone's
+    /// available resource, before any work is allocated.
+    pub fn apply_holidays(&mut self) -> Result<()> {
+
+        let periods: Vec<ChartPeriod> = self.holidays.iter().filter_map(|h| self.clip_to_chart(h)).collect();
+        for dev in self.developers.values_mut() {
+            if dev.contractor.is_none() {
+                for period in &periods {
+                    dev.cells.unset_range(period)?;
+                }
+            }
+            dev.available = dev.cells.clone();
+        }
+        Ok(())
+    }
+
+    /// Limit a period to the chart.  Leave and holidays are often listed
+    /// beyond the end of the plan.
+    fn clip_to_chart(&self, period: &ChartPeriod) -> Option<ChartPeriod> {
+        if self.weeks == 0 {
+            return None;
+        }
+        period.intersect(&ChartPeriod::new(0, self.weeks * 20 - 1).unwrap())
+    }
+
+    /// Parse a list of times or periods, such as "12/3..12/5, 20".
+    pub fn parse_periods(&self, desc: &str) -> Result<Vec<ChartPeriod>> {
+
+        let mut periods = Vec::new();
+        for part in desc.split(",").map(|p| p.trim()) {
+            if part.contains("..") {
+                periods.push(self.parse_period(part)?);
+            } else {
+                let ct = self.parse_time(part)?;
+                periods.push(ChartPeriod::new(ct.to_u32(), ct.end_as_u32())?);
+            }
+        }
+
+        Ok(periods)
+    }
+
     pub fn get_dev_data<'a, 'b>(&'a mut self, name: &'b str) -> Option<&'a mut DeveloperData> {
         if !self.developers.contains_key(name) {
             return None;
@@ -226,16 +720,42 @@ impl RootConfigData {
         return Some(self.developers[name].period);
     }
 
-    pub fn get_plan_dev_duration(&self, dev: &Option<String>) -> u32 {
+    /// The most quarter days of work that a developer can do in each week,
+    /// or None for a week without limit.
+    pub fn get_dev_weekly_available(&self, name: &str) -> Vec<Option<u32>> {
+        match self.developers.get(name) {
+            Some(dev) => dev.get_weekly_available(),
+            None => vec![Some(0); self.weeks as usize],
+        }
+    }
 
-        let mut duration = self.weeks * 20;
-        if let Some(ref d) = *dev {
-            if let Some(period) = self.get_dev_period(&d) {
-                duration = period.length();
-            }
+    /// The day rate for a developer, or 0 if they have none.
+    pub fn get_dev_rate(&self, name: &str) -> f32 {
+        self.developers.get(name).map_or(0.0, |d| d.rate)
+    }
+
+    /// The number of cells in the chart for which work on a task with the
+    /// given devs can happen.
+    pub fn get_plan_dev_duration(&self, devs: &[String]) -> u32 {
+
+        if self.weeks == 0 {
+            return 0;
         }
+        self.count_devs_present(devs, &ChartPeriod::new(0, self.weeks * 20 - 1).unwrap())
+    }
 
-        duration
+    /// Count the cells in a period during which at least one of the devs
+    /// is present.  If none of them is known, that is the whole period.
+    pub fn count_devs_present(&self, devs: &[String], period: &ChartPeriod) -> u32 {
+
+        let periods: Vec<ChartPeriod> = devs.iter().filter_map(|d| self.get_dev_period(d)).collect();
+        if periods.len() == 0 {
+            return period.length();
+        }
+
+        (period.get_first()..period.get_last() + 1)
+            .filter(|&cell| periods.iter().any(|p| cell >= p.get_first() && cell <= p.get_last()))
+            .count() as u32
     }
 
     pub fn is_valid_developer(&self, name: &str) -> bool {
@@ -247,10 +767,11 @@ impl RootConfigData {
     }
 
     // Work out the future, weekly resource needed to manage the non-managers, then 
-    // transfer it from the manager to the row passed in. 
+    // transfer it from the manager to the row passed in.  Only resource within
+    // the window passed in is transferred.
     //
     // Caller is responsible for checking that there is a manager configured.
-    pub fn transfer_management_resource(&mut self, mut row: &mut ChartRow) -> Result<()> {
+    pub fn transfer_management_resource(&mut self, mut row: &mut ChartRow, window: &ChartPeriod) -> Result<()> {
 
         let quarters_in_chart = self.get_weeks() * 20;
         let remaining_period = ChartPeriod::new(self.get_now(), quarters_in_chart-1).unwrap();
@@ -267,7 +788,7 @@ impl RootConfigData {
 
         for q in 0 .. quarters_in_chart {
 
-            if q < self.get_now() {
+            if q < self.get_now() || q < window.get_first() || q > window.get_last() {
                 continue;
             }
 
@@ -287,14 +808,16 @@ impl RootConfigData {
 
             weekly_resource += quarterly_resource;
 
-            // If this was the last day of the week, do the resource transfer
-            if q % 20 == 19 {
+            // If this was the last day of the week (or of the window), do the
+            // resource transfer
+            if q % 20 == 19 || q == window.get_last() {
 
                 for (dev, ref mut data) in self.developers.iter_mut() {
                     if *dev == manager {
+                        let week = ChartPeriod::new(q - q % 20, q).unwrap();
                         let transfer_result = data.cells.fill_transfer_to(&mut row,
                                                                          weekly_resource.ceil() as u32,
-                                                                         &ChartPeriod::new(q-19, q).unwrap())?;
+                                                                         &week.limit_first(window.get_first()).unwrap())?;
 
                         total_failures += transfer_result.failed;
                     }
@@ -312,17 +835,34 @@ impl RootConfigData {
         Ok(())
     }
 
-    // Handle any "nodes" that define config at the root level
+    // Handle any "nodes" that define config at the root level.  Problems
+    // with the start date or length of the chart stop the plan, as they
+    // define its shape.  Other problems are recorded, and the rest of the
+    // config read.
     pub fn read_config(&mut self, mut config: &mut file::ConfigLines) -> Result<()> {
 
-        if let Some(file::Line::Node(file::LineNode { line_num: _, indent: _, name })) =
-            config.get_line() {
+        if let Some(file::Line::Node(line)) = config.get_line() {
+
+            let node_pos = Some(config.get_pos() - 1);
+            for d in config.get_errors_after(node_pos).iter().chain(&config.get_warnings_after(node_pos)) {
+                self.add_error(&d.summary());
+            }
+
+            // Dates and periods in [devs] and [holidays] depend on the start
+            // date and length of the chart.
+            let c = ROOT_NODE_RE.captures(&line.name).unwrap();
+            if &c["name"] != "global" && !self.sections.iter().any(|&(ref l, _)| l.name == "[global]") {
+                bail!(ErrorKind::Diagnostic(config.diagnostic(&line.span,
+                                                              &format!("{} must come after [global]", line.name))));
+            }
+            self.sections.push((line.clone(), config.peek_attributes()));
 
-            let c = ROOT_NODE_RE.captures(&name).unwrap();
             if &c["name"] == "global" {
                 self.read_global_config(&mut config).chain_err(|| "Failed to read [global] node")?;
             } else if &c["name"] == "devs" {
                 self.read_devs_config(&mut config).chain_err(|| "Failed to read [devs] node")?;
+            } else if &c["name"] == "holidays" {
+                self.read_holidays_config(&mut config).chain_err(|| "Failed to read [holidays] node")?;
             } else {
                 bail!("Internal error: Unexpected node type");
             }
@@ -336,53 +876,174 @@ impl RootConfigData {
 
     /// Store any configuration stored under [global]
     fn read_global_config(&mut self, config: &mut file::ConfigLines) -> Result<()> {
-        while let Some(file::Line::Attribute(file::LineAttribute { key, value })) =
-            config.peek_line() {
 
-            config.get_line();
+        // Dates are relative to the start date, so pick that up first.
+        let mut attributes: Vec<file::LineAttribute> = Vec::new();
+        while let Some(file::Line::Attribute(attribute)) = config.peek_line() {
 
-            if key == "weeks" {
-                let weeks = value.parse::<u32>()
-                    .chain_err(|| "Error parsing \"weeks\" from [chart] node")?;
-
-                self.set_weeks(weeks);
-            } else if key == "now" {
-                let ct = value.parse::<ChartTime>()
-                    .chain_err(|| "Error parsing \"now\" from [chart] node")?;
-                self.set_now(ct.to_u32());
-            } else if key == "manager" {
-                self.set_manager(&value);
-            } else if key == "label" {
-                self.add_label(&value).chain_err(|| "Failed to add label")?;
-            } else if key == "start-date" {
-                let dt = value.parse::<ChartDate>()
-                    .chain_err(|| "Error parsing \"start-date\" from [chart] node")?;
-                self.set_start_date(&dt);
+            config.get_line();
+            if attribute.key == "start-date" {
+                self.read_start_date(&attribute.value)
+                    .chain_err(|| ErrorKind::Diagnostic(config.diagnostic(&attribute.span, "Failed to read \"start-date\"")))?;
             } else {
-                bail!(format!("Unrecognised attribute \"{}\" in [chart] node", key));
+                attributes.push(attribute);
             }
         }
+
+        for attribute in attributes {
+            let result = self.read_global_attribute(&attribute.key, &attribute.value)
+                .chain_err(|| ErrorKind::Diagnostic(config.diagnostic(&attribute.span,
+                                                                      &format!("Failed to read \"{}\"", attribute.key))));
+            if let Err(e) = result {
+                if attribute.key == "weeks" {
+                    return Err(e);
+                }
+                self.add_error(&render_error_summary(&e));
+            }
+        }
+
+        // Work can always be outsourced, without limit, for the whole chart,
+        // whether or not there is a [devs] node.
+        if self.weeks != 0 && !self.developers.contains_key("outsource") {
+            let chart_period = ChartPeriod::new(0, self.weeks * 20 - 1).unwrap();
+            self.add_developer("outsource", &chart_period)?;
+            self.set_contractor("outsource", "unlimited")?;
+        }
+        Ok(())
+    }
+
+    fn read_start_date(&mut self, value: &str) -> Result<()> {
+        let dt = value.parse::<ChartDate>()
+            .chain_err(|| "Error parsing \"start-date\" from [chart] node")?;
+        self.set_start_date(&dt);
+        Ok(())
+    }
+
+    fn read_global_attribute(&mut self, key: &str, value: &str) -> Result<()> {
+        if key == "weeks" {
+            let weeks = value.parse::<u32>()
+                .chain_err(|| "Error parsing \"weeks\" from [chart] node")?;
+
+            self.set_weeks(weeks);
+        } else if key == "now" {
+            let ct = self.parse_time(&value)
+                .chain_err(|| "Error parsing \"now\" from [chart] node")?;
+            self.set_now(ct.to_u32());
+        } else if key == "manager" {
+            self.set_manager(&value);
+        } else if key == "label" {
+            self.add_label(&value).chain_err(|| "Failed to add label")?;
+        } else {
+            bail!(format!("Unrecognised attribute \"{}\" in [chart] node", key));
+        }
         Ok(())
     }
 
     /// Store any configuration stored under [devs]
     fn read_devs_config(&mut self, config: &mut file::ConfigLines) -> Result<()> {
-        while let Some(file::Line::Attribute(file::LineAttribute { key, value })) =
+
+        while let Some(file::Line::Attribute(file::LineAttribute { key, value, span, .. })) =
             config.peek_line() {
 
             config.get_line();
-            let cp = value.parse::<ChartPeriod>()
-                    .chain_err(|| format!("Error parsing \"time range\" for \"{}\" in [devs] node", key))?;
-            self.add_developer(&key, &cp).chain_err(|| format!("Error adding \"{}\" in [devs] node", key))?;
+            let result = self.read_devs_attribute(&key, &value)
+                .chain_err(|| ErrorKind::Diagnostic(config.diagnostic(&span, &format!("Failed to read \"{}\"", key))));
+            if let Err(ref e) = result {
+                self.add_error(&render_error_summary(e));
+            }
         }
 
         // Check that the manager has been defined
         if let Some(ref manager) = self.get_manager() {
             if !self.is_valid_developer(manager) {
-                bail!(format!("Manager \"{}\" not defined as a dev", manager));
+                self.add_error(&format!("Manager \"{}\" not defined as a dev", manager));
             }
         }
 
         Ok(())
     }
+
+    fn read_devs_attribute(&mut self, key: &str, value: &str) -> Result<()> {
+
+        // Settings for a dev are keyed "<dev>-<setting>".  Anything else,
+        // including a name that happens to contain a '-', defines a dev.
+        let mut parts = key.rsplitn(2, '-');
+        let setting = parts.next().unwrap();
+        let dev = match parts.next() {
+            Some(dev) if self.developers.contains_key(dev) => Some(dev),
+            _ => None
+        };
+        if let Some(dev) = dev {
+            match setting {
+                "leave" => {
+                    let periods = self.parse_periods(&value)
+                        .chain_err(|| format!("Error parsing leave for \"{}\" in [devs] node", dev))?;
+                    for period in periods {
+                        self.add_leave(dev, &period).chain_err(|| format!("Error adding leave for \"{}\" in [devs] node", dev))?;
+                    }
+                    return Ok(());
+                },
+                "capacity" => {
+                    return self.set_capacity(dev, &value)
+                        .chain_err(|| format!("Error setting capacity for \"{}\" in [devs] node", dev));
+                },
+                "tags" => {
+                    return self.set_tags(dev, &value)
+                        .chain_err(|| format!("Error setting tags for \"{}\" in [devs] node", dev));
+                },
+                "contractor" => {
+                    return self.set_contractor(dev, &value)
+                        .chain_err(|| format!("Error setting contractor capacity for \"{}\" in [devs] node", dev));
+                },
+                "rate" => {
+                    return self.set_rate(dev, &value)
+                        .chain_err(|| format!("Error setting day rate for \"{}\" in [devs] node", dev));
+                },
+                _ => {}
+            }
+        }
+
+        // "outsource" is predefined, but may be given a period like
+        // anyone else.
+        if key == "outsource" {
+            self.developers.remove("outsource");
+        }
+
+        let cp = self.parse_period(&value)
+                .chain_err(|| format!("Error parsing \"time range\" for \"{}\" in [devs] node", key))?;
+        self.add_developer(&key, &cp).chain_err(|| format!("Error adding \"{}\" in [devs] node", key))?;
+        if key == "outsource" {
+            self.set_contractor(&key, "unlimited")?;
+        }
+
+        Ok(())
+    }
+
+    /// Store any configuration stored under [holidays].  Each entry names
+    /// a holiday, and gives the days it covers.
+    fn read_holidays_config(&mut self, config: &mut file::ConfigLines) -> Result<()> {
+        while let Some(file::Line::Attribute(file::LineAttribute { key, value, span, .. })) =
+            config.peek_line() {
+
+            config.get_line();
+            let result = self.read_holiday(&key, &value)
+                .chain_err(|| ErrorKind::Diagnostic(config.diagnostic(&span, &format!("Failed to read \"{}\"", key))));
+            if let Err(ref e) = result {
+                self.add_error(&render_error_summary(e));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_holiday(&mut self, key: &str, value: &str) -> Result<()> {
+
+        let periods = self.parse_periods(&value)
+                .chain_err(|| format!("Error parsing \"{}\" in [holidays] node", key))?;
+        for period in periods {
+            self.add_holiday(&period);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,99 @@
+// The public entry point for loading and scheduling a plan.
+use std::cell::RefCell;
+
+use typed_arena;
+use arena_tree;
+
+use errors::*;
+use file;
+use nodes;
+use chart;
+use chart::TemplateContext;
+use api;
+use schedule;
+use writer;
+
+/// A plan read from a config file, ready to be scheduled.
+///
+/// The nodes of the plan are allocated in an arena owned by the plan, so
+/// a scheduled plan can't outlive the plan it came from.
+pub struct Plan<'a> {
+    config: file::ConfigLines,
+    arena: typed_arena::Arena<arena_tree::Node<'a, RefCell<nodes::ConfigNode>>>,
+}
+
+impl<'a> Plan<'a> {
+    /// Read and parse the config file.
+    pub fn load(filename: &str) -> Result<Plan<'a>> {
+
+        let config =
+            file::ConfigLines::new_from_file(filename).chain_err(|| "Failed to read config")?;
+        Ok(Plan { config: config, arena: typed_arena::Arena::new() })
+    }
+
+    /// Build the node tree, and allocate resource to every node in it.
+    ///
+    /// Errors in the config cause the scheduling to be abandoned.  Problems
+    /// found while allocating resource are attached to the affected nodes
+    /// instead.
+    pub fn schedule(&'a self) -> Result<ScheduledPlan<'a>> {
+
+        let mut config = self.config.clone();
+        let root = nodes::ConfigNode::new_from_config(&self.arena, &mut config, None, true, 0)
+            .chain_err(|| "Failed to set up nodes")?;
+
+        schedule::schedule_nodes(root)?;
+
+        Ok(ScheduledPlan { root: root })
+    }
+
+    /// Write the config back out in a standard layout.  Lines that can't
+    /// be parsed would be lost, so any are reported as errors instead.
+    /// Lines with warnings are written as they were read; see `warnings`.
+    pub fn format(&'a self) -> Result<String> {
+
+        let errors: Vec<String> = self.config.get_errors().iter().map(|d| d.summary()).collect();
+        if errors.len() != 0 {
+            bail!(format!("Unable to format a config with errors:\n{}", errors.join("\n")));
+        }
+
+        let mut config = self.config.clone();
+        let root = nodes::ConfigNode::new_from_config(&self.arena, &mut config, None, true, 0)
+            .chain_err(|| "Failed to set up nodes")?;
+
+        Ok(writer::write_config(root))
+    }
+
+    /// Describe the lines that were read, but may not mean what was
+    /// intended, such as those whose indent mixes tabs and spaces.
+    pub fn warnings(&self) -> Vec<String> {
+        self.config.get_warnings().iter().map(|d| d.summary()).collect()
+    }
+}
+
+/// A plan with resource allocated to every node.
+pub struct ScheduledPlan<'a> {
+    root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>,
+}
+
+impl<'a> ScheduledPlan<'a> {
+    /// Describe the whole plan, with the figures and notes for every node.
+    pub fn report(&self) -> Result<api::PlanReport> {
+        api::generate_plan_report(self.root).chain_err(|| "Error generating output")
+    }
+
+    /// Describe the slack on every scheduled leaf task, and the critical path.
+    pub fn critical_path(&self) -> api::CriticalPathReport {
+        api::generate_critical_path_report(self.root)
+    }
+
+    /// The rows of the chart, ready to be rendered.
+    pub fn chart(&self, base_url: &str) -> Result<TemplateContext> {
+        chart::generate_chart_context(self.root, base_url).chain_err(|| "Error generating output")
+    }
+
+    /// The rows of the chart for a single developer, ready to be rendered.
+    pub fn dev_chart(&self, dev: &str, base_url: &str) -> Result<TemplateContext> {
+        chart::generate_dev_chart_context(self.root, dev, base_url).chain_err(|| "Error generating output")
+    }
+}
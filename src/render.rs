@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use handlebars::Handlebars;
+
+use planner3::errors::*;
+use planner3::api;
+use planner3::TemplateContext;
+use planner3::Plan;
+
+/// Output formats supported by `planner3 render`.
+pub enum OutputFormat {
+    Html,
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<OutputFormat> {
+        match s {
+            "html" => Ok(OutputFormat::Html),
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => bail!(format!("Unknown format \"{}\" - expected html, text or json", s)),
+        }
+    }
+}
+
+// The web server's template, built in so that rendering works from any
+// directory
+const INDEX_TEMPLATE: &'static str = include_str!("../templates/index.html.hbs");
+
+/// Render the chart using the same template as the web server, without
+/// needing Rocket to be running.
+fn render_html(context: &TemplateContext) -> Result<String> {
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_string("index", INDEX_TEMPLATE)
+        .map_err(|e| format!("Failed to load template: {}", e))?;
+    let html = handlebars.render("index", context)
+        .map_err(|e| format!("Failed to render template: {}", e))?;
+    Ok(html)
+}
+
+/// Schedule the plan in the config file, and render the chart in the
+/// requested format.
+pub fn render_plan(filename: &str, format: &OutputFormat) -> Result<String> {
+
+    if let OutputFormat::Json = *format {
+        return api::get_plan_json(filename);
+    }
+
+    // There is no server behind the page, so it has no links
+    let plan = Plan::load(filename)?;
+    let context = plan.schedule()?.chart("")?;
+    match *format {
+        OutputFormat::Html => render_html(&context),
+        _ => Ok(context.generate_text()),
+    }
+}
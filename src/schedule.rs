@@ -0,0 +1,753 @@
+// The pipeline that derives information through the node tree, and then
+// transfers resource to each node.
+use std::cell::RefCell;
+
+use arena_tree;
+use regex::Regex;
+
+use errors::*;
+use nodes;
+use nodes::root::RootConfigData;
+use nodes::data::ResourcingStrategy;
+use validate;
+
+/// Describe a chained error, one cause per line
+fn describe_error(e: &Error) -> String {
+
+    let mut error: String = format!("{}", e);
+    for e in e.iter().skip(1) {
+        error = format!("{}\ncaused by: {}", error, e);
+    }
+
+    error
+}
+
+/// Update the dev information on a node, if necessary inheriting information
+/// from ancestors.
+fn derive_dev<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    // Scan back up the tree, looking for an answer.  Shared work is split
+    // in the same way as on the node that names the devs.  A pool is
+    // inherited in the same way, if no dev is named first.
+    let mut dev: Option<(String, Vec<u32>)> = None;
+    let mut pool: Option<String> = None;
+    for n in node.ancestors() {
+
+        // Avoid the root node - it is already borrowed.
+        if n.parent().is_none() {
+            break;
+        }
+
+        let node_name = n.data.borrow().name.clone();
+        if let Some(ref node_data) = n.data.borrow().node_data {
+            if let Some(ref d) = node_data.get_dev(root_data, &node_name) {
+                dev = Some((d.clone(), node_data.get_split().clone()));
+                break;
+            }
+            if let Some(p) = node_data.get_pool() {
+                pool = Some(p);
+                break;
+            }
+        }
+    }
+
+    if let Some((d, split)) = dev {
+        if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+            node_data.set_dev(root_data, &d).chain_err(|| "Failed to derive developer")?;
+            if node_data.get_split().len() == 0 {
+                node_data.set_split_percentages(&split);
+            }
+        }
+    } else if let Some(p) = pool {
+        if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+            node_data.set_pool(&p).chain_err(|| "Failed to derive pool")?;
+        }
+    }
+
+    Ok(())
+}    
+
+/// Mark a node as not managed if any of its ancestors isn't.
+fn derive_non_managed<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    // Scan back up the tree, looking for an ancestor that isn't managed
+    for n in node.ancestors().skip(1) {
+
+        // Avoid the root node - it is already borrowed.
+        if n.parent().is_none() {
+            break;
+        }
+
+        if let Some(ref node_data) = n.data.borrow().node_data {
+            if !node_data.get_managed() {
+                if let Some(ref mut this_node_data) = node.data.borrow_mut().node_data {
+                    this_node_data.set_managed(false);
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the resourcing information on a node, if necessary inheriting information
+/// from ancestors.
+fn derive_resourcing<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    // Scan back up the tree, looking for an answer.
+    let mut resourcing: Option<ResourcingStrategy> = None;
+    for n in node.ancestors() {
+
+        // Avoid the root node - it is already borrowed.
+        if n.parent().is_none() {
+            break;
+        }
+
+        let node_name = n.data.borrow().name.clone();
+        if let Some(ref node_data) = n.data.borrow().node_data {
+            if let Some(r) = node_data.get_resourcing(root_data, &node_name) {
+                resourcing = Some(r);
+                break;
+            }
+        }
+    }
+
+    if let Some(r) = resourcing {
+        if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+            node_data.set_resourcing(root_data, r).chain_err(|| "Failed to derive resourcing")?;
+        }
+    }
+
+    Ok(())
+}    
+
+/// Narrow the earliest-start / latest-end window on a node to fit within
+/// the windows of all of its ancestors.
+fn derive_window<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let mut earliest_start = 0u32;
+    let mut latest_end = root_data.get_weeks() * 20;
+    for n in node.ancestors().skip(1) {
+
+        // Avoid the root node - it is already borrowed.
+        if n.parent().is_none() {
+            break;
+        }
+
+        if let Some(ref node_data) = n.data.borrow().node_data {
+            if node_data.get_earliest_start() > earliest_start {
+                earliest_start = node_data.get_earliest_start();
+            }
+            if node_data.get_latest_end() < latest_end {
+                latest_end = node_data.get_latest_end();
+            }
+        }
+    }
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.limit_window(earliest_start, latest_end);
+    }
+
+    Ok(())
+}    
+
+/// Find the plan information on a node, if necessary inheriting information
+/// from ancestors.
+pub fn find_plan_at_time<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b RootConfigData, when: u32) -> Result<Option<u32>> {
+
+    // First off, look in this node's plan 
+    let node_name = node.data.borrow().name.clone();
+    if let Some(ref node_data) = node.data.borrow().node_data {
+        let devs = node_data.get_devs(root_data, &node_name);
+        if let Some(p) = node_data.get_plan(root_data, &devs, when) {
+            return Ok(Some(p));
+        }
+
+        // If the node has a dev, scan back up the tree, looking for a default plan
+        if devs.len() != 0 {
+            for n in node.ancestors().skip(1) {
+
+                // Avoid the root node - it is already borrowed.
+                if n.parent().is_none() {
+                    break;
+                }
+                
+                if let Some(ref node_data) = n.data.borrow().node_data {
+                    if let Some(p) = node_data.get_default_plan(root_data, &devs, when) {
+                        return Ok(Some(p));
+                    }
+                }
+            }
+        }
+    }
+
+
+    Ok(None)
+}    
+
+
+
+/// Set the initial and current plan on a node, if necessary inheriting a
+/// default plan from ancestors.
+fn derive_plan<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let p1 = find_plan_at_time(node, root_data, 0).chain_err(|| "Failed to get initial plan")?;
+    let p2 = find_plan_at_time(node, root_data, root_data.get_now()).chain_err(|| "Failed to get current plan")?;
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_derived_plan(p1, p2).chain_err(|| "Failed to set plan")?;
+    }
+
+    Ok(())
+}    
+
+/// List every problem in the plan: those in the root config, then those
+/// on each node, labelled with the line of the node.
+pub fn collect_problems<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Vec<String> {
+
+    // Avoid unnecessary recompilation of the regular expressions
+    lazy_static! {
+        static ref LOCATED_RE: Regex = Regex::new(r"^\S+:\d+:\d+: ").unwrap();
+    }
+
+    let mut problems: Vec<String> = Vec::new();
+    let root_node = root.data.borrow();
+    if let Some(ref root_data) = root_node.root_data {
+        problems.extend(root_data.get_errors().iter().cloned());
+
+        // Problems found reading the config already give their location
+        for n in root.descendants().skip(1) {
+            let node = n.data.borrow();
+            if let Some(ref node_data) = node.node_data {
+                for error in node_data.get_errors() {
+                    if LOCATED_RE.is_match(error) {
+                        problems.push(error.clone());
+                    } else {
+                        problems.push(format!("{}: {}: {}",
+                                              root_data.describe_location(node.file, node.line_num),
+                                              node.name,
+                                              error));
+                    }
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Describe the path to a node, as used to identify it in "after".
+pub fn node_path<'a>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> String {
+
+    let mut names: Vec<String> = Vec::new();
+    for n in node.ancestors() {
+
+        // Avoid the root node - it is already borrowed.
+        if n.parent().is_none() {
+            break;
+        }
+
+        names.insert(0, n.data.borrow().name.clone());
+    }
+
+    names.join("/")
+}
+
+/// Find the node defined at the given line of the given config file.
+fn find_node_at_line<'a>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, file: usize, line_num: u32)
+    -> Option<&'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>> {
+
+    let root = node.ancestors().last().unwrap();
+    root.descendants().skip(1).find(|n| {
+        let config_node = n.data.borrow();
+        config_node.file == file && config_node.line_num == line_num
+    })
+}
+
+fn is_same_node<'a>(a: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>,
+                    b: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> bool {
+    a as *const _ == b as *const _
+}
+
+/// Resolve the task names given in "after" against the node tree.  Names
+/// can be given as a path of node names, separated by "/", and only need
+/// enough of the path to be unambiguous.
+fn derive_after<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let after = match node.data.borrow().node_data {
+        Some(ref node_data) => node_data.get_after().clone(),
+        None => Vec::new(),
+    };
+    if after.len() == 0 {
+        return Ok(());
+    }
+
+    let root = node.ancestors().last().unwrap();
+    let mut lines: Vec<(usize, u32)> = Vec::new();
+    for name in &after {
+        let suffix = format!("/{}", name);
+        let found: Vec<_> = root.descendants()
+            .skip(1)
+            .filter(|n| {
+                        let path = node_path(n);
+                        path == *name || path.ends_with(&suffix)
+                    })
+            .collect();
+
+        if found.len() == 0 {
+            bail!(format!("Task \"{}\" in after not found", name));
+        }
+        if found.len() > 1 {
+            let matches: Vec<String> = found.iter()
+                .map(|n| root_data.describe_line(n.data.borrow().file, n.data.borrow().line_num))
+                .collect();
+            bail!(format!("Task \"{}\" in after is ambiguous; it matches lines {}", name, matches.join(", ")));
+        }
+
+        let dep = found[0];
+        if node.ancestors().any(|n| is_same_node(n, dep)) || dep.ancestors().any(|n| is_same_node(n, node)) {
+            bail!(format!("Task \"{}\" in after is this task, or one of its ancestors or descendants", name));
+        }
+
+        let dep_node = dep.data.borrow();
+        lines.push((dep_node.file, dep_node.line_num));
+    }
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_after_lines(lines);
+    }
+
+    Ok(())
+}
+
+/// Find the nodes that must be complete before this node can start: the
+/// preceding siblings of any serially scheduled ancestor, and any nodes
+/// named in "after" on this node or its ancestors.
+fn find_predecessors<'a>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>)
+    -> Vec<&'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>> {
+
+    let mut predecessors = Vec::new();
+    for n in node.ancestors() {
+
+        // Avoid the root node - it is already borrowed.
+        let parent = match n.parent() {
+            Some(p) => p,
+            None => break,
+        };
+
+        if let Some(ref node_data) = n.data.borrow().node_data {
+            for &(file, line_num) in node_data.get_after_lines() {
+                if let Some(dep) = find_node_at_line(node, file, line_num) {
+                    predecessors.push(dep);
+                }
+            }
+        }
+
+        if parent.parent().is_none() {
+            break;
+        }
+
+        let serial = parent.data.borrow().node_data.as_ref().map_or(false, |d| d.is_serial());
+        if serial {
+            let mut sibling = n.previous_sibling();
+            while let Some(s) = sibling {
+                predecessors.push(s);
+                sibling = s.previous_sibling();
+            }
+        }
+    }
+
+    predecessors
+}
+
+/// Find the first cell that future resource can be allocated to on this
+/// node, which is after the last cell allocated to any of its predecessors.
+fn find_sequence_start<'a>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> u32 {
+
+    let mut start = 0u32;
+    for p in find_predecessors(node) {
+        for d in p.descendants() {
+            if let Some(ref node_data) = d.data.borrow().node_data {
+                if let Some(latest) = node_data.get_latest_allocated() {
+                    if latest + 1 > start {
+                        start = latest + 1;
+                    }
+                }
+            }
+        }
+    }
+
+    start
+}
+
+/// Order the nodes so that each node comes after all of its predecessors,
+/// otherwise preserving the order in which they are defined.  Fails if the
+/// dependencies contain a cycle.
+fn dependency_order<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>)
+    -> Result<Vec<&'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>>> {
+
+    let nodes: Vec<_> = root.descendants().skip(1).collect();
+    let index_of = |n: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>| {
+        nodes.iter().position(|x| is_same_node(x, n)).unwrap()
+    };
+
+    // For each node, the indices of the nodes that must be scheduled first
+    let mut waiting_for: Vec<Vec<usize>> = Vec::new();
+    for n in &nodes {
+        let mut indices = Vec::new();
+        for p in find_predecessors(n) {
+            for d in p.descendants() {
+                indices.push(index_of(d));
+            }
+        }
+        waiting_for.push(indices);
+    }
+
+    let mut done = vec![false; nodes.len()];
+    let mut order = Vec::new();
+    while order.len() < nodes.len() {
+        let next = (0..nodes.len()).find(|i| !done[*i] && waiting_for[*i].iter().all(|w| done[*w]));
+        if let Some(i) = next {
+            done[i] = true;
+            order.push(nodes[i]);
+            continue;
+        }
+
+        // Everything left is blocked; walk back through the blockers
+        // until we find a node twice, to report the cycle.
+        let mut cycle: Vec<usize> = Vec::new();
+        let mut blocked = (0..nodes.len()).find(|i| !done[*i]);
+        while let Some(i) = blocked {
+            if cycle.contains(&i) {
+                break;
+            }
+            cycle.push(i);
+            blocked = waiting_for[i].iter().cloned().find(|w| !done[*w]);
+        }
+        let i = match blocked {
+            Some(i) => i,
+            None => bail!("Internal error: no cycle found between the blocked tasks"),
+        };
+        let first = cycle.iter().position(|x| *x == i).unwrap();
+        let describe = |x: usize| {
+            let config_node = nodes[x].data.borrow();
+            match root.data.borrow().root_data {
+                Some(ref root_data) => root_data.describe_line(config_node.file, config_node.line_num),
+                None => config_node.line_num.to_string()
+            }
+        };
+        let mut lines: Vec<String> = cycle[first..].iter().map(|x| describe(*x)).collect();
+        lines.push(describe(i));
+        bail!(format!("Dependency cycle between tasks at lines {}", lines.join(" -> ")));
+    }
+
+    Ok(order)
+}
+
+/// Transfer the work done before now to a node, from the resource of the
+/// devs who did it.
+fn transfer_past_done<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.transfer_past_done(root_data).chain_err(|| "Failed to transfer done resource")?;
+    }
+
+    Ok(())
+}    
+
+/// Transfer work recorded as done from now on to a managed node.
+fn transfer_future_done_managed<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.transfer_future_done_managed(root_data).chain_err(|| "Failed to transfer done resource")?;
+    }
+
+    Ok(())
+}    
+
+/// Transfer work recorded as done from now on to a node that isn't
+/// managed.
+fn transfer_future_done_unmanaged<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.transfer_future_done_unmanaged(root_data).chain_err(|| "Failed to transfer done resource")?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate future resource to a node with a smeared resourcing strategy,
+/// and the smeared part of prodsfr, after any predecessors finish.
+fn transfer_future_smear<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let sequence_start = find_sequence_start(node);
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_sequence_start(sequence_start);
+        node_data.transfer_future_smear(root_data)?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate future resource to a frontloaded node, after any predecessors
+/// finish.
+fn transfer_future_frontload<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let sequence_start = find_sequence_start(node);
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_sequence_start(sequence_start);
+        node_data.transfer_future_frontload(root_data)?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate future resource to a backloaded node, and the backloaded part
+/// of prodsfr, after any predecessors finish.
+fn transfer_future_backload<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let sequence_start = find_sequence_start(node);
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_sequence_start(sequence_start);
+        node_data.transfer_future_backload(root_data)?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate any future resource that a node still needs, by its resourcing
+/// strategy, after any predecessors finish.
+fn transfer_future_remaining_resource<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let sequence_start = find_sequence_start(node);
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_sequence_start(sequence_start);
+        node_data.transfer_future_remaining_resource(root_data)?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate future resource to a node that isn't managed, before any
+/// managed work, after any predecessors finish.
+fn transfer_future_unmanaged_resource<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let sequence_start = find_sequence_start(node);
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.set_sequence_start(sequence_start);
+        node_data.transfer_future_unmanaged_resource(root_data)?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate the manager's future time to a management node, as needed to
+/// manage the rest of the team.
+fn transfer_future_management_resource<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
+        node_data.transfer_future_management_resource(root_data)?;
+    }
+
+    Ok(())
+}    
+
+/// Allocate future resource to a node by its resourcing strategy.  Every
+/// strategy is run on the node at once, so that it is fully allocated
+/// before any node that depends on it finds its sequence start.
+fn transfer_future_strategies<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+
+    let results = vec![transfer_future_smear(node, root_data),
+                       transfer_future_backload(node, root_data),
+                       transfer_future_frontload(node, root_data),
+                       transfer_future_remaining_resource(node, root_data)];
+    for result in results {
+        result?;
+    }
+
+    Ok(())
+}
+
+/// Find which of the passed nodes are linked to others by dependencies,
+/// either because they have predecessors or because they are one.
+fn find_linked_nodes<'a>(nodes: &Vec<&'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>>) -> Vec<bool> {
+
+    let mut linked = vec![false; nodes.len()];
+    for (i, n) in nodes.iter().enumerate() {
+        for p in find_predecessors(n) {
+            linked[i] = true;
+            for d in p.descendants() {
+                if let Some(j) = nodes.iter().position(|x| is_same_node(x, d)) {
+                    linked[j] = true;
+                }
+            }
+        }
+    }
+
+    linked
+}
+
+/// Return the first and last cells allocated to a node.
+pub fn allocated_period<'a>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Option<(u32, u32)> {
+
+    if let Some(ref node_data) = node.data.borrow().node_data {
+        if let (Some(e), Some(l)) = (node_data.get_earliest_allocated(), node_data.get_latest_allocated()) {
+            return Some((e, l));
+        }
+    }
+
+    None
+}
+
+/// Work out how far each scheduled leaf task can slip before it pushes out
+/// its latest-end, the end of the chart, or a task that depends on it.  Then
+/// mark the chain of tasks whose finish sets the end of the project.
+fn calculate_critical_path<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<()> {
+
+    let (now, quarters_in_chart) = match root.data.borrow().root_data {
+        Some(ref root_data) => (root_data.get_now(), root_data.get_weeks() * 20),
+        None => bail!("No root data defined"),
+    };
+
+    let tasks: Vec<_> = dependency_order(root)?
+        .into_iter()
+        .filter(|n| n.first_child().is_none() && allocated_period(n).is_some())
+        .collect();
+    let predecessors: Vec<Vec<usize>> = tasks.iter()
+        .map(|t| {
+            let mut indices = Vec::new();
+            for p in find_predecessors(t) {
+                for d in p.descendants() {
+                    if let Some(i) = tasks.iter().position(|x| is_same_node(x, d)) {
+                        indices.push(i);
+                    }
+                }
+            }
+            indices
+        })
+        .collect();
+
+    // Work backwards through the tasks, so that any successors of a task
+    // have their slack calculated first.
+    let mut slack: Vec<i32> = vec![0; tasks.len()];
+    for i in (0..tasks.len()).rev() {
+        let (_, finish) = allocated_period(tasks[i]).unwrap();
+        let mut latest_end = quarters_in_chart - 1;
+        if let Some(ref node_data) = tasks[i].data.borrow().node_data {
+            if node_data.get_latest_end() < latest_end {
+                latest_end = node_data.get_latest_end();
+            }
+        }
+
+        slack[i] = latest_end as i32 - finish as i32;
+        for j in i + 1..tasks.len() {
+            if predecessors[j].contains(&i) {
+                let (start, _) = allocated_period(tasks[j]).unwrap();
+                let start = if start < now { now } else { start };
+                let successor_slack = start as i32 + slack[j] - 1 - finish as i32;
+                if successor_slack < slack[i] {
+                    slack[i] = successor_slack;
+                }
+            }
+        }
+
+        if let Some(ref mut node_data) = tasks[i].data.borrow_mut().node_data {
+            node_data.set_slack(slack[i]);
+        }
+    }
+
+    // Walk back from the last task to finish, through its latest finishing
+    // predecessors.
+    let finish = |i: &usize| allocated_period(tasks[*i]).unwrap().1;
+    let mut current = (0..tasks.len()).rev().max_by_key(&finish);
+    while let Some(i) = current {
+        if let Some(ref mut node_data) = tasks[i].data.borrow_mut().node_data {
+            node_data.set_critical();
+        }
+        current = predecessors[i].iter().cloned().rev().max_by_key(&finish);
+    }
+
+    Ok(())
+}
+
+/// Call the passed function on all descendants of the passed node.
+fn call_on_children<'a, F>(node_fn: F, root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<()>
+    where F: for<'x, 'y> Fn(&'x arena_tree::Node<'x, RefCell<nodes::ConfigNode>>, &'y mut RootConfigData) -> Result<()> {
+
+    let nodes: Vec<_> = root.descendants().skip(1).collect();
+    call_on_nodes(node_fn, root, &nodes)
+}
+
+/// Call the passed function on each of the passed nodes, in order.
+fn call_on_nodes<'a, F>(node_fn: F,
+                        root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>,
+                        nodes: &Vec<&'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>>) -> Result<()>
+    where F: for<'x, 'y> Fn(&'x arena_tree::Node<'x, RefCell<nodes::ConfigNode>>, &'y mut RootConfigData) -> Result<()> {
+
+    let mut root_node = root.data.borrow_mut();
+    if let Some(ref mut root_data) = root_node.root_data {
+
+        // Run the passed function on each node.  Write any errors to the node itself.
+        for child in nodes {
+            if let Err(ref e) = node_fn(child, root_data) {
+                if let Some(ref mut node_data) = child.data.borrow_mut().node_data {
+                    node_data.add_error(&describe_error(e));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Allocate resource to every node in the plan.
+pub fn schedule_nodes<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<()> {
+
+    // Nobody is available on a holiday, wherever it was listed
+    if let Some(ref mut root_data) = root.data.borrow_mut().root_data {
+        root_data.apply_holidays().chain_err(|| "Failed to apply holidays")?;
+    }
+
+    // Set up derived info in the node heirarchy
+    call_on_children(derive_dev, &root).chain_err(|| "Failed to derive dev information")?;
+    call_on_children(derive_non_managed, &root).chain_err(|| "Failed to derive dev information")?;
+    call_on_children(derive_resourcing, &root).chain_err(|| "Failed to derive plan information")?;
+    call_on_children(derive_window, &root).chain_err(|| "Failed to derive earliest-start/latest-end information")?;
+    call_on_children(derive_after, &root).chain_err(|| "Failed to derive after information")?;
+
+    // Check the invariants that the allocation relies on
+    validate::validate_nodes(&root)?;
+
+    // Resource is transferred to tasks in dependency order
+    let order = dependency_order(&root).chain_err(|| "Failed to order tasks by dependency")?;
+    call_on_nodes(transfer_past_done, &root, &order).chain_err(|| "Failed to transfer past done resource")?;
+    call_on_children(derive_plan, &root).chain_err(|| "Failed to derive plan information")?;
+    call_on_nodes(transfer_future_done_unmanaged, &root, &order).chain_err(|| "Failed to transfer done resource")?;
+    call_on_nodes(transfer_future_unmanaged_resource, &root, &order).chain_err(|| "Failed to transfer future resource")?;
+    call_on_nodes(transfer_future_management_resource, &root, &order).chain_err(|| "Failed to transfer future resource")?;
+    call_on_nodes(transfer_future_done_managed, &root, &order).chain_err(|| "Failed to transfer done resource")?;
+
+    // Smeared resource is allocated across the whole plan first, then
+    // backloaded, then frontloaded.  Tasks linked by dependencies are the
+    // exception: they are allocated by every strategy at once, in the smear
+    // pass, so that a task's successors start after all of its resource.
+    let linked = find_linked_nodes(&order);
+    for (node, is_linked) in order.iter().zip(&linked) {
+        let nodes = vec![*node];
+        if *is_linked {
+            call_on_nodes(transfer_future_strategies, &root, &nodes).chain_err(|| "Failed to transfer future resource")?;
+        } else {
+            call_on_nodes(transfer_future_smear, &root, &nodes).chain_err(|| "Failed to transfer future smeared resource")?;
+        }
+    }
+
+    let unlinked: Vec<_> = order.iter().zip(&linked).filter(|&(_, l)| !*l).map(|(n, _)| *n).collect();
+    call_on_nodes(transfer_future_backload, &root, &unlinked).chain_err(|| "Failed to transfer future backloaded resource")?;
+    call_on_nodes(transfer_future_frontload, &root, &unlinked).chain_err(|| "Failed to transfer future frontloaded resource")?;
+    
+    call_on_nodes(transfer_future_remaining_resource, &root, &unlinked).chain_err(|| "Failed to transfer future resource")?;
+
+    calculate_critical_path(&root).chain_err(|| "Failed to calculate the critical path")?;
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// Checks on the node tree, made before any resource is allocated.
+use std::cell::RefCell;
+
+use arena_tree;
+
+use errors::*;
+use nodes;
+use nodes::data::ResourcingStrategy;
+use schedule::find_plan_at_time;
+
+/// Check the invariants that scheduling relies on:
+///
+/// - only leaf nodes have done data
+/// - all leaf nodes have plan and dev data
+/// - all devs are valid devs
+/// - no plan is given on a node with children
+/// - done work falls within the dev's time range
+/// - latest-end is not before earliest-start
+///
+/// Dev, resourcing and window information must already have been derived
+/// from the ancestors.  Every problem is recorded on the node at fault, and
+/// listed with the other problems in the plan.
+pub fn validate_nodes<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<()> {
+
+    let root_node = root.data.borrow();
+    let root_data = root_node.root_data.as_ref().ok_or("No root data defined")?;
+    let end_of_chart = root_data.get_weeks() * 20;
+
+    for n in root.descendants().skip(1) {
+        let is_leaf = n.first_child().is_none();
+        let name = n.data.borrow().name.clone();
+        let mut node_problems: Vec<String> = Vec::new();
+
+        if let Some(ref node_data) = n.data.borrow().node_data {
+            node_problems.extend(node_data.validate(root_data, is_leaf));
+
+            if is_leaf {
+                if node_data.get_dev(root_data, &name).is_none() && node_data.get_pool().is_none() {
+                    node_problems.push("No dev for this task".to_string());
+                }
+
+                // Management tasks are sized from the rest of the plan
+                if node_data.get_resourcing(root_data, &name) != Some(ResourcingStrategy::Management) {
+                    match find_plan_at_time(n, root_data, end_of_chart) {
+                        Ok(Some(_)) => {}
+                        Ok(None) => node_problems.push("No plan for this task".to_string()),
+                        Err(ref e) => node_problems.push(render_error_summary(e)),
+                    }
+                }
+            }
+        }
+
+        if let Some(ref mut node_data) = n.data.borrow_mut().node_data {
+            for problem in node_problems {
+                node_data.add_error(&problem);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,575 +1,313 @@
-use std::cell::RefCell;
+// The routes aren't built for the unit tests, which leaves the code and
+// imports that they use unused.
+#![cfg_attr(test, allow(dead_code, unused_imports))]
+
+use std::fs;
+use std::path::PathBuf;
+use regex::Regex;
 
 use rocket;
+use rocket::response::content;
+use rocket::State;
 use rocket_contrib::Template;
-use typed_arena;
-use arena_tree;
+use serde_json;
 
-use errors::*;
-use nodes;
-use nodes::root::{RootConfigData, BorderType};
-use nodes::data::ResourcingStrategy;
-use file;
+use planner3::errors::*;
+use planner3::api;
+use planner3::Plan;
 
 #[derive(Serialize)]
-pub struct TemplateRow {
-    what: String,
-    who: String,
-    line_num: u32,
-    done: String,
-    left: String,
-    plan: String,
-    gain: String,
-    even: bool,
-    add_style: String,
-    notes: Vec<String>,
-    notes_html: String,
-    cells: Vec<(String, String)>,
+pub struct ErrorTemplate {
+    error: String,
 }
 
-impl TemplateRow {
-    pub fn new(indent: u32, line_num: u32, name: &str) -> TemplateRow {
-        TemplateRow {
-            // Indent is 0 on the resource rows
-            what: if indent == 0 { name.to_string() } else {
-                  format!("{}{}",
-                            &format!("{:width$}", " ", width = (indent * 3) as usize),
-                          name)
-                    .replace(" ", "&nbsp;") },
-            who: "".to_string(),
-            done: " ".to_string(),
-            gain: " ".to_string(),
-            add_style: if indent == 1 { " budget".to_string() }
-                       else { String::new() },
-            line_num: line_num,
-            left: " ".to_string(),
-            plan: " ".to_string(),
-            even: false,
-            cells: Vec::new(),
-            notes: Vec::new(),
-            notes_html: String::new(),
-        }
-    }
-
-    pub fn set_who(&mut self, who: &str) {
-        self.who = who.to_string();
-    }
-
-    fn format_f32(val: f32) -> String {
-        if val.abs() < 0.01 {
-            String::new()
-        } else {
-            format!("{:.2}", val).replace(".00", "&nbsp;&nbsp;&nbsp;").replace(".50", ".5&nbsp;")
-        }
-    }
-
-    pub fn add_cell(&mut self, root: &RootConfigData, val: f32) {
-        let mut style = TemplateContext::cell_border_style(root, 1+self.cells.len() as u32);
+#[derive(Serialize)]
+pub struct PlansTemplate {
+    plan_dir: String,
+    plans: Vec<String>,
+}
 
-        self.cells.push((style, TemplateRow::format_f32(val)));
-    }
+/// Configuration for the web server
+pub struct WebConfig {
 
-    pub fn add_note(&mut self, val: &str) {
-        self.notes.push(val.to_string());
-    }
+    // Directory containing the plans that can be served.  If not set, only
+    // the default config file is served.
+    plan_dir: Option<PathBuf>,
+}
 
-    pub fn set_done(&mut self, done: f32) {
-        self.done = TemplateRow::format_f32(done);
+impl WebConfig {
+    pub fn new(plan_dir: Option<PathBuf>) -> WebConfig {
+        WebConfig { plan_dir: plan_dir }
     }
 
-    pub fn set_gain(&mut self, gain: f32) {
-        self.gain = TemplateRow::format_f32(gain);
-    }
+    /// Check that a plan name is safe to use as a file name, ie that it
+    /// can't be used to reach files outside the plan directory.
+    fn is_valid_plan_name(name: &str) -> bool {
 
-    pub fn set_left(&mut self, left: f32) {
-        self.left = TemplateRow::format_f32(left);
-    }
+        // Avoid unnecessary recompilation of the regular expressions
+        lazy_static! {
+            static ref PLAN_NAME_RE: Regex = Regex::new(r"^[\w\-]+$").unwrap();
+        }
 
-    pub fn set_plan(&mut self, plan: f32) {
-        self.plan = TemplateRow::format_f32(plan);
+        PLAN_NAME_RE.is_match(name)
     }
 
-    fn prepare_html(&mut self) {
+    /// Find the config file for the named plan.
+    fn plan_file(&self, name: &str) -> Result<String> {
 
-        self.notes_html = String::new();
-        if self.notes.len() == 0 {
-            return;
+        let plan_dir = self.plan_dir.as_ref().ok_or("No plan directory configured")?;
+        if !WebConfig::is_valid_plan_name(name) {
+            bail!(format!("Invalid plan name \"{}\"", name));
         }
 
-        let mut first = true;
-        for note in &self.notes {
-            // @@@ Improve formatting on multi-line notes
-            if !first {
-                self.notes_html.push_str("<br>");
-            }
-
-            self.notes_html.push_str(&note);
-
-            first = false;
+        // Belt and braces - check that the file really is in the plan directory.
+        let dir = plan_dir.canonicalize().chain_err(|| format!("Failed to find plan directory {}", plan_dir.display()))?;
+        let path = dir.join(format!("{}.txt", name))
+            .canonicalize()
+            .chain_err(|| format!("Plan \"{}\" not found", name))?;
+        if !path.starts_with(&dir) {
+            bail!(format!("Plan \"{}\" is outside the plan directory", name));
         }
-    }
-}
-
-#[derive(Serialize, Default)]
-pub struct TemplateContext {
-    // Tuples of (style, content)
-    cell_headers: Vec<(String, String)>,
 
-    // Tuples of (colspan, style, content)
-    cell_labels: Vec<(u32, String, String)>,
-
-    resource_rows: Vec<TemplateRow>,
-    rows: Vec<TemplateRow>,
-
-}
-
-impl TemplateContext {
-
-    fn cell_border_style(root: &RootConfigData, week: u32) -> String {
-        match root.weekly_left_border(week) {
-            BorderType::None => "grid".to_string(),
-            BorderType::Start => "grid border".to_string(),
-            BorderType::Now => "grid start".to_string(),
-            BorderType::Label => "grid label".to_string(),
-        }
+        Ok(path.to_string_lossy().into_owned())
     }
 
-    pub fn new(root: &RootConfigData) -> TemplateContext {
+    /// List the plans available in the plan directory.
+    fn list_plans(&self) -> Result<Vec<String>> {
 
-        //let mut t = TemplateContext { cell_headers: Vec::new(), cell_labels: Vec::new(), rows: Vec::new(), top_height: 60, left_width: 600 };
-        let mut t = TemplateContext { ..Default::default() };
-
-        // Set up the header details
-        for s in 1..root.get_weeks() + 1 {
-            let style = TemplateContext::cell_border_style(root, s);
-            t.cell_headers.push((style, s.to_string()));
-        }
-
-        // Set up the row of labels
-        let mut colspan = 0;
-        let mut last_style: Option<String> = None;
-        let mut last_note: Option<String> = None;
-        for s in 1..root.get_weeks() + 1 {
-            let style = TemplateContext::cell_border_style(root, s);
-            colspan += 1;
-            if style != "grid" {
-                // Complete the current span
-                if let Some(style) = last_style {
-                    if let Some(note) = last_note {
-                        t.cell_labels.push((colspan, style, note));
-                    } else {
-                        t.cell_labels.push((colspan, style, "".to_string()));
+        let plan_dir = self.plan_dir.as_ref().ok_or("No plan directory configured")?;
+        let mut plans = Vec::new();
+        for entry in fs::read_dir(plan_dir).chain_err(|| format!("Failed to read plan directory {}", plan_dir.display()))? {
+            let path = entry.chain_err(|| "Failed to read plan directory entry")?.path();
+            if path.extension().map_or(false, |e| e == "txt") {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    if self.plan_file(name).is_ok() {
+                        plans.push(name.to_string());
                     }
                 }
-
-                // Start on the next span
-                last_style = Some(style);
-                last_note = root.weekly_label(s);
-                colspan = 0;
             }
         }
 
-        // Complete the current span
-        if let Some(note) = last_note {
-            t.cell_labels.push((colspan+1, last_style.unwrap(), note));
-        } else {
-            t.cell_labels.push((colspan+1, last_style.unwrap(), "".to_string()));
-        }
-        
-        t
-    }
-
-    pub fn add_resource_row(&mut self, mut row: TemplateRow) {
-        row.even = self.resource_rows.len() % 2 == 1;
-        self.resource_rows.push(row);
-    }
-
-    pub fn add_row(&mut self, mut row: TemplateRow) {
-        row.even = (self.rows.len() + self.resource_rows.len()) % 2 == 1;
-        self.rows.push(row);
-    }
-
-    fn prepare_html(&mut self) {
-        for row in &mut self.resource_rows {
-            row.prepare_html();
-        }
-        for row in &mut self.rows {
-            row.prepare_html();
-        }
+        plans.sort();
+        Ok(plans)
     }
 }
 
+/// Config file used when no plan directory is configured
+const DEFAULT_CONFIG: &'static str = "config.txt";
 
-fn generate_weekly_output<'a, 'b, 'c>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, 
-                          root_data: &'c RootConfigData,
-                          mut context: &'b mut TemplateContext) -> Result<()> {
-
-    let name = node.data.borrow().name.clone();
-    let line_num = node.data.borrow().line_num;
-    let level = node.data.borrow().level;
-    if let Some(ref node_data) = node.data.borrow().node_data {
-        node_data.generate_weekly_output(root_data, name, line_num, level, &mut context)?;
-    } else {
-        bail!("Internal error - no node_data");
-    }
-
-    for child in node.children() {
-        generate_weekly_output(child, root_data, context)?;
-    }
+fn get_index_html(filename: &str, base_url: &str) -> Result<Template> {
 
-    Ok(())
+    let plan = Plan::load(filename)?;
+    let context = plan.schedule()?.chart(base_url)?;
+    Ok(Template::render("index", &context))
 }
 
-#[cfg(not(test))]
-fn generate_chart_html<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<Template> {
-
-    let root_node = root.data.borrow();
-    if let Some(ref root_data) = root_node.root_data {
-        let mut context = TemplateContext::new(root_data);
-        root_data.generate_dev_weekly_output(&mut context);
-
-        for child in root.children() {
-            generate_weekly_output(child, root_data, &mut context)?;
-        }
-
-        // Do any required preparation before rendering
-        context.prepare_html();
+fn get_dev_html(filename: &str, dev: &str, base_url: &str) -> Result<Template> {
 
-        return Ok(Template::render("index", &context));
-    }
-    bail!("No root data defined");
+    let plan = Plan::load(filename)?;
+    let context = plan.schedule()?.dev_chart(dev, base_url)?;
+    Ok(Template::render("index", &context))
 }
 
+fn get_plans_html(config: &WebConfig) -> Result<Template> {
 
-#[derive(Serialize)]
-pub struct ErrorTemplate {
-    error: String,
+    let plans = config.list_plans()?;
+    let plan_dir = config.plan_dir.as_ref().map_or(String::new(), |d| d.display().to_string());
+    Ok(Template::render("plans", &PlansTemplate { plan_dir: plan_dir, plans: plans }))
 }
 
-/// Update the dev information on a node, if necessary inheriting information
-/// from ancestors.
-fn derive_dev<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
-
-    // Scan back up the tree, looking for an answer.
-    let mut dev: Option<String> = None;
-    for n in node.ancestors() {
-
-        // Avoid the root node - it is already borrowed.
-        if n.parent().is_none() {
-            break;
-        }
-
-        let node_name = n.data.borrow().name.clone();
-        if let Some(ref node_data) = n.data.borrow().node_data {
-            if let Some(ref d) = node_data.get_dev(root_data, &node_name) {
-                dev = Some(d.clone());
-                break;
-            }
-        }
-    }
-
-    if let Some(d) = dev {
-        if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-            node_data.set_dev(root_data, &d).chain_err(|| "Failed to derive developer")?;
-        }
-    }
-
-    Ok(())
-}    
-
-/// Update the dev information on a node, if necessary inheriting information
-/// from ancestors.
-fn derive_non_managed<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
-
-    // Scan back up the tree, looking for evidence that 
-    for n in node.ancestors().skip(1) {
+/// Unwrap the chained error into one big string, pointing at the config
+/// line at fault if known.
+#[cfg(not(test))]
+fn generate_error_text(e: &Error) -> String {
 
-        // Avoid the root node - it is already borrowed.
-        if n.parent().is_none() {
-            break;
-        }
+    let mut error = render_error(e);
 
-        if let Some(ref node_data) = n.data.borrow().node_data {
-            if !node_data.get_managed() {
-                if let Some(ref mut this_node_data) = node.data.borrow_mut().node_data {
-                    this_node_data.set_managed(false);
-                    break;
-                }
-            }
-        }
+    // The backtrace is not always generated. Try to run this example
+    // with `RUST_BACKTRACE=1`.
+    if let Some(backtrace) = e.backtrace() {
+        error = format!("{}\n\nbacktrace: {:?}", error, backtrace);
     }
 
-    Ok(())
+    error
 }
 
-/// Update the resourcingv information on a node, if necessary inheriting information
-/// from ancestors.
-fn derive_resourcing<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
-
-    // Scan back up the tree, looking for an answer.
-    let mut resourcing: Option<ResourcingStrategy> = None;
-    for n in node.ancestors() {
-
-        // Avoid the root node - it is already borrowed.
-        if n.parent().is_none() {
-            break;
-        }
-
-        let node_name = n.data.borrow().name.clone();
-        if let Some(ref node_data) = n.data.borrow().node_data {
-            if let Some(r) = node_data.get_resourcing(root_data, &node_name) {
-                resourcing = Some(r);
-                break;
-            }
-        }
-    }
-
-    if let Some(r) = resourcing {
-        if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-            node_data.set_resourcing(root_data, r).chain_err(|| "Failed to derive resourcing")?;
-        }
-    }
-
-    Ok(())
-}    
-
-/// Find the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn find_plan_at_time<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b RootConfigData, when: u32) -> Result<Option<u32>> {
-
-    // First off, look in this node's plan 
-    let node_name = node.data.borrow().name.clone();
-    if let Some(ref node_data) = node.data.borrow().node_data {
-        let dev: Option<String> = node_data.get_dev(root_data, &node_name);
-        if let Some(p) = node_data.get_plan(root_data, &dev, when) {
-            return Ok(Some(p));
-        }
-
-        // If the node has a dev, scan back up the tree, looking for a default plan
-        if let Some(ref d) = dev {
-            for n in node.ancestors().skip(1) {
-
-                // Avoid the root node - it is already borrowed.
-                if n.parent().is_none() {
-                    break;
-                }
-                
-                if let Some(ref node_data) = n.data.borrow().node_data {
-                    if let Some(p) = node_data.get_default_plan(root_data, &Some(d.clone()), when) {
-                        return Ok(Some(p));
-                    }
-                }
-            }
-        }
-    }
-
+/// Unwrap the chained error into one big string, and display it.
+#[cfg(not(test))]
+fn generate_error_page(e: &Error) -> Template {
 
-    Ok(None)
-}    
+    Template::render("err", &ErrorTemplate { error: generate_error_text(e) })
+}
 
+#[cfg(not(test))]
+fn generate_error_json(e: &Error) -> content::JSON<String> {
 
+    let error = ErrorTemplate { error: generate_error_text(e) };
+    content::JSON(serde_json::to_string(&error).unwrap_or(String::new()))
+}
 
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn derive_plan<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+/// Show the default plan, or if there is a plan directory, list the plans
+/// in it.
+#[cfg(not(test))]
+#[get("/")]
+fn index(config: State<WebConfig>) -> Template {
 
-    let p1 = find_plan_at_time(node, root_data, 0).chain_err(|| "Failed to get initial plan")?;
-    let p2 = find_plan_at_time(node, root_data, root_data.get_now()).chain_err(|| "Failed to get current plan")?;
+    let result = if config.plan_dir.is_some() {
+        get_plans_html(&config)
+    } else {
+        get_index_html(DEFAULT_CONFIG, "/")
+    };
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.set_derived_plan(p1, p2).chain_err(|| "Failed to set plan")?;
+    match result {
+        Ok(template) => template,
+        Err(e) => generate_error_page(&e)
     }
 
-    Ok(())
-}    
+}
 
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_past_done<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/dev/<name>")]
+fn dev_index(name: String) -> Template {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_past_done(root_data).chain_err(|| "Failed to transfer done resource")?;
+    match get_dev_html(DEFAULT_CONFIG, &name, "/") {
+        Ok(template) => template,
+        Err(e) => generate_error_page(&e)
     }
+}
 
-    Ok(())
-}    
-
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_done_managed<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/critical-path")]
+fn critical_path() -> content::JSON<String> {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_done_managed(root_data).chain_err(|| "Failed to transfer done resource")?;
+    match api::get_critical_path_json(DEFAULT_CONFIG) {
+        Ok(json) => content::JSON(json),
+        Err(e) => generate_error_json(&e)
     }
+}
 
-    Ok(())
-}    
-
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_done_unmanaged<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/api/plan")]
+fn api_plan() -> content::JSON<String> {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_done_unmanaged(root_data).chain_err(|| "Failed to transfer done resource")?;
+    match api::get_plan_json(DEFAULT_CONFIG) {
+        Ok(json) => content::JSON(json),
+        Err(e) => generate_error_json(&e)
     }
+}
 
-    Ok(())
-}    
-
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_smear<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/plan/<plan>")]
+fn plan_index(config: State<WebConfig>, plan: String) -> Template {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_smear(root_data)?;
+    let result = config.plan_file(&plan)
+        .and_then(|f| get_index_html(&f, &format!("/plan/{}/", plan)));
+    match result {
+        Ok(template) => template,
+        Err(e) => generate_error_page(&e)
     }
+}
 
-    Ok(())
-}    
-
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_frontload<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/plan/<plan>/dev/<name>")]
+fn plan_dev_index(config: State<WebConfig>, plan: String, name: String) -> Template {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_frontload(root_data)?;
+    let result = config.plan_file(&plan)
+        .and_then(|f| get_dev_html(&f, &name, &format!("/plan/{}/", plan)));
+    match result {
+        Ok(template) => template,
+        Err(e) => generate_error_page(&e)
     }
+}
 
-    Ok(())
-}    
-
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_backload<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/plan/<plan>/critical-path")]
+fn plan_critical_path(config: State<WebConfig>, plan: String) -> content::JSON<String> {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_backload(root_data)?;
+    match config.plan_file(&plan).and_then(|f| api::get_critical_path_json(&f)) {
+        Ok(json) => content::JSON(json),
+        Err(e) => generate_error_json(&e)
     }
+}
 
-    Ok(())
-}    
-
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_remaining_resource<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+#[cfg(not(test))]
+#[get("/plan/<plan>/api/plan")]
+fn plan_api_plan(config: State<WebConfig>, plan: String) -> content::JSON<String> {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_remaining_resource(root_data)?;
+    match config.plan_file(&plan).and_then(|f| api::get_plan_json(&f)) {
+        Ok(json) => content::JSON(json),
+        Err(e) => generate_error_json(&e)
     }
+}
 
-    Ok(())
-}    
+#[cfg(not(test))]
+pub fn serve_web(config: WebConfig) {
+    rocket::ignite()
+        .manage(config)
+        .mount("/",
+               routes![index, dev_index, critical_path, api_plan, plan_index, plan_dev_index, plan_critical_path, plan_api_plan])
+        .launch();
+}
 
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_unmanaged_resource<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+/// The unit tests don't build the routes, so there is no server to run.
+#[cfg(test)]
+pub fn serve_web(_config: WebConfig) {}
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_unmanaged_resource(root_data)?;
-    }
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::path::PathBuf;
 
-    Ok(())
-}    
+    use super::WebConfig;
 
-/// Update the plan information on a node, if necessary inheriting information
-/// from ancestors.
-fn transfer_future_management_resource<'a, 'b>(node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, root_data: &'b mut RootConfigData) -> Result<()> {
+    /// Make an empty plan directory for the test, returning the directory
+    /// that contains it.
+    fn make_plan_dir(test: &str) -> PathBuf {
 
-    if let Some(ref mut node_data) = node.data.borrow_mut().node_data {
-        node_data.transfer_future_management_resource(root_data)?;
+        let dir = env::temp_dir().join("planner3-web-tests").join(test);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("plans")).unwrap();
+        dir
     }
 
-    Ok(())
-}    
-
-/// Call the passed function on all descendants of the passed node.
-fn call_on_children<'a, F>(node_fn: F, root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> Result<()>
-    where F: for<'x, 'y> Fn(&'x arena_tree::Node<'x, RefCell<nodes::ConfigNode>>, &'y mut RootConfigData) -> Result<()> {
-
-    let mut root_node = root.data.borrow_mut();
-    if let Some(ref mut root_data) = root_node.root_data {
+    #[test]
+    fn plan_names_are_single_file_names() {
 
-        // Run the passed function on each node.  Write any errors to the node itself.
-        for child in root.descendants().skip(1) {
-            if let Err(ref e) = node_fn(child, root_data) {
-                if let Some(ref mut node_data) = child.data.borrow_mut().node_data {
-                    node_data.add_note(&generate_error_html(e))?;
-                }
-            }
-        }
+        assert!(WebConfig::is_valid_plan_name("q3-plan_2"));
+        assert!(!WebConfig::is_valid_plan_name(".."));
+        assert!(!WebConfig::is_valid_plan_name("a/b"));
+        assert!(!WebConfig::is_valid_plan_name("../plans/q3"));
+        assert!(!WebConfig::is_valid_plan_name(""));
     }
-    Ok(())
-}
 
-fn get_index_html() -> Result<Template> {
-
-    // While reading and parsing the config, we generate errors, which cause
-    // the processing to be abandoned.
-    let mut config =
-        file::ConfigLines::new_from_file("config.txt").chain_err(|| "Failed to read config")?;
-    let arena = typed_arena::Arena::new();
-    let root = nodes::ConfigNode::new_from_config(&arena, &mut config, None, true, 0)
-        .chain_err(|| "Failed to set up nodes")?;
-
-    // Set up derived info in the node heirarchy
-    call_on_children(derive_dev, &root).chain_err(|| "Failed to derive dev information")?;
-    call_on_children(derive_non_managed, &root).chain_err(|| "Failed to derive dev information")?;
-    call_on_children(derive_resourcing, &root).chain_err(|| "Failed to derive plan information")?;
-    call_on_children(transfer_past_done, &root).chain_err(|| "Failed to transfer past done resource")?;
-    call_on_children(derive_plan, &root).chain_err(|| "Failed to derive plan information")?;
-    call_on_children(transfer_future_done_unmanaged, &root).chain_err(|| "Failed to transfer done resource")?;
-    call_on_children(transfer_future_unmanaged_resource, &root).chain_err(|| "Failed to transfer future resource")?;
-    call_on_children(transfer_future_management_resource, &root).chain_err(|| "Failed to transfer future resource")?;
-    call_on_children(transfer_future_done_managed, &root).chain_err(|| "Failed to transfer done resource")?;
-
-    call_on_children(transfer_future_smear, &root).chain_err(|| "Failed to transfer future smeared resource")?;
-    call_on_children(transfer_future_backload, &root).chain_err(|| "Failed to transfer future backloaded resource")?;
-    call_on_children(transfer_future_frontload, &root).chain_err(|| "Failed to transfer future frontloaded resource")?;
-    
-    call_on_children(transfer_future_remaining_resource, &root).chain_err(|| "Failed to transfer future resource")?;
-
-    // Only critical errors from now on.  Further problems are displayed in the chart.
-    let template = generate_chart_html(&root).chain_err(|| "Error generating output")?;
-    Ok(template)
-}
+    #[test]
+    fn plan_file_is_found_in_the_plan_directory() {
 
-/// Unwrap the chained error into one big string
-#[cfg(not(test))]
-fn generate_error_html(e: &Error) -> String {
+        let dir = make_plan_dir("plan_file");
+        File::create(dir.join("plans").join("q3.txt")).unwrap();
+        File::create(dir.join("secret.txt")).unwrap();
+        let config = WebConfig::new(Some(dir.join("plans")));
 
-    let mut error: String = format!("{}", e);
-    for e in e.iter().skip(1) {
-        error = format!("{}<br>caused by: {}", error, e);
+        assert!(config.plan_file("q3").unwrap().ends_with("q3.txt"));
+        assert!(config.plan_file("..").unwrap_err().to_string().contains("Invalid plan name"));
+        assert!(config.plan_file("a/b").unwrap_err().to_string().contains("Invalid plan name"));
+        assert!(config.plan_file("missing").unwrap_err().to_string().contains("not found"));
     }
 
-    // The backtrace is not always generated. Try to run this example
-    // with `RUST_BACKTRACE=1`.
-    if let Some(backtrace) = e.backtrace() {
-        error = format!("{}<br><br>backtrace: {:?}", error, backtrace);
-    }
+    #[cfg(unix)]
+    #[test]
+    fn plan_file_refuses_a_link_out_of_the_plan_directory() {
 
-    error
-}
+        use std::os::unix::fs::symlink;
 
-/// Unwrap the chained error into one big string, and display it.
-#[cfg(not(test))]
-fn generate_error_page(e: &Error) -> Template {
+        let dir = make_plan_dir("plan_link");
+        File::create(dir.join("secret.txt")).unwrap();
+        symlink(dir.join("secret.txt"), dir.join("plans").join("leak.txt")).unwrap();
+        let config = WebConfig::new(Some(dir.join("plans")));
 
-    Template::render("err", &ErrorTemplate { error: generate_error_html(e) })
-}
-
-#[cfg(not(test))]
-#[get("/")]
-fn index() -> Template {
-
-    match get_index_html() {
-        Ok(template) => template,
-        Err(e) => generate_error_page(&e)
+        assert!(config.plan_file("leak").unwrap_err().to_string().contains("outside the plan directory"));
+        assert_eq!(config.list_plans().unwrap(), Vec::<String>::new());
     }
-
-}
-
-#[cfg(not(test))]
-pub fn serve_web() {
-    rocket::ignite().mount("/", routes![index]).launch();
 }
@@ -0,0 +1,162 @@
+// Writing the node tree back out as config text, in a standard layout.
+use std::cell::RefCell;
+
+use arena_tree;
+use regex::Regex;
+
+use file::{Extra, LineNode, LineAttribute};
+use nodes;
+
+// Spaces of indent for each level of the tree
+const INDENT: usize = 2;
+
+struct ConfigWriter {
+    output: String,
+
+    // Top-level nodes are separated by a blank line, written before the
+    // next line of output.
+    blank_line_due: bool,
+}
+
+impl ConfigWriter {
+    fn write_line(&mut self, indent: usize, text: &str, comment: &Option<String>) {
+
+        if self.blank_line_due && !self.output.is_empty() {
+            self.output.push_str("\n");
+        }
+        self.blank_line_due = false;
+
+        self.output.push_str(&format!("{:width$}{}", "", text, width = indent));
+        if let Some(ref comment) = *comment {
+            self.output.push_str(&format!("  {}", comment));
+        }
+        self.output.push_str("\n");
+    }
+
+    /// Write the comments and includes that belong to the file being
+    /// written.  Those from included files are left where they are.
+    fn write_extras(&mut self, extras: &[Extra], indent: usize) {
+
+        for extra in extras {
+            match *extra {
+                Extra::Comment { file: 0, ref text } => self.write_line(indent, text, &None),
+                Extra::Include { file: 0, ref path, ref comment } => {
+                    self.write_line(indent, &format!("include: {}", path), comment)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_attribute(&mut self, attribute: &LineAttribute, indent: usize) {
+
+        // Values over several lines are written as a block
+        if attribute.value.contains('\n') {
+            self.write_line(indent, &format!("- {}: |", attribute.key), &attribute.comment);
+            for line in attribute.value.lines() {
+                if line.is_empty() {
+                    self.output.push_str("\n");
+                } else {
+                    self.write_line(indent + INDENT * 2, line, &None);
+                }
+            }
+        } else {
+            self.write_line(indent,
+                            &format!("- {}: {}", attribute.key, quote_value(&attribute.value)),
+                            &attribute.comment);
+        }
+    }
+
+    /// Write a node and its attributes, skipping any lines that came from
+    /// an included file.
+    fn write_node(&mut self, line: &LineNode, attributes: &[LineAttribute], depth: usize) {
+
+        let indent = depth * INDENT;
+        if depth == 0 {
+            self.blank_line_due = true;
+        }
+
+        self.write_extras(&line.before, indent);
+        if line.span.file == 0 {
+            self.write_line(indent, &quote_name(&line.name), &line.comment);
+        }
+
+        for attribute in attributes {
+            self.write_extras(&attribute.before, indent + INDENT);
+            if attribute.span.file == 0 {
+                self.write_attribute(attribute, indent + INDENT);
+            }
+        }
+    }
+
+    fn write_tree<'a>(&mut self, node: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>, depth: usize) {
+
+        {
+            let config_node = node.data.borrow();
+            if let Some(ref line) = config_node.source {
+                self.write_node(line, &config_node.attributes, depth);
+            }
+        }
+
+        for child in node.children() {
+            self.write_tree(child, depth + 1);
+        }
+    }
+}
+
+/// Put text in quotes, escaping any quotes and backslashes in it.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace("\\", "\\\\").replace("\"", "\\\""))
+}
+
+/// Quote an attribute value if it would otherwise be read differently.
+fn quote_value(value: &str) -> String {
+
+    if value == "|" || value != value.trim() || value.contains(|c| c == '#' || c == '"' || c == '\\') {
+        quote(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quote a node name if it would otherwise be read differently, or taken
+/// for an attribute or an include.
+fn quote_name(name: &str) -> String {
+
+    // Avoid unnecessary recompilation of the regular expressions
+    lazy_static! {
+        static ref INCLUDE_RE: Regex = Regex::new(r"^include\s*:").unwrap();
+    }
+
+    if name.is_empty() || name != name.trim() || name.starts_with('-') || INCLUDE_RE.is_match(name) ||
+       name.contains(|c| c == '#' || c == '"' || c == '\\') {
+        quote(name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Write out the config that the node tree was read from, in a standard
+/// layout: the [global], [devs] and [holidays] nodes first, then the plan,
+/// with two spaces of indent for each level and a blank line before each
+/// top-level node.  Attributes and comments are kept as they were.
+///
+/// Only the file named on the command line is written.  Anything read
+/// from an included file is written as the include that brought it in.
+pub fn write_config<'a>(root: &'a arena_tree::Node<'a, RefCell<nodes::ConfigNode>>) -> String {
+
+    let mut writer = ConfigWriter { output: String::new(), blank_line_due: false };
+    let root_node = root.data.borrow();
+    if let Some(ref root_data) = root_node.root_data {
+        for &(ref section, ref attributes) in root_data.get_sections() {
+            writer.write_node(section, attributes, 0);
+        }
+    }
+
+    for child in root.children() {
+        writer.write_tree(child, 0);
+    }
+
+    writer.write_extras(&root_node.trailing, 0);
+    writer.output
+}
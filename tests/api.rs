@@ -0,0 +1,43 @@
+// The JSON served at /api/plan.
+extern crate planner3;
+extern crate serde_json;
+
+mod common;
+
+use common::{header, write_file};
+use planner3::api;
+
+#[test]
+fn plan_json_describes_devs_and_nested_nodes() {
+
+    let path = write_file("api_plan", "plan.txt", &format!("{}
+Project
+  - dev: alice
+  - resource: frontload
+  Build
+    - plan: 3
+", header(2, &["alice"])));
+    let json = api::get_plan_json(path.to_str().unwrap()).unwrap();
+    let plan: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(plan["weeks"].as_u64(), Some(2));
+    assert_eq!(plan["problems"].as_array().unwrap().len(), 0);
+
+    let alice = plan["devs"].as_array().unwrap().iter().find(|d| d["name"].as_str() == Some("alice")).unwrap();
+    assert_eq!(alice["weeks"].as_array().unwrap().len(), 2);
+
+    let nodes = plan["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0]["name"].as_str(), Some("Project"));
+    assert_eq!(nodes[0]["level"].as_u64(), Some(1));
+    assert_eq!(nodes[0]["plan"].as_f64(), Some(3.0));
+
+    let build = &nodes[0]["children"][0];
+    assert_eq!(build["name"].as_str(), Some("Build"));
+    assert_eq!(build["line_num"].as_u64(), Some(10));
+    assert_eq!(build["dev"].as_str(), Some("alice"));
+    assert_eq!(build["resourcing"].as_str(), Some("frontload"));
+    let weeks: Vec<f64> = build["weeks"].as_array().unwrap().iter().map(|w| w.as_f64().unwrap()).collect();
+    assert_eq!(weeks, vec![3.0, 0.0]);
+    assert_eq!(build["children"].as_array().unwrap().len(), 0);
+}
@@ -0,0 +1,72 @@
+// Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use planner3::api::{PlanNode, PlanReport};
+use planner3::Plan;
+
+/// Write a file into a directory of its own for the test, returning its
+/// path.
+pub fn write_file(test: &str, name: &str, text: &str) -> PathBuf {
+
+    let path = env::temp_dir().join("planner3-tests").join(test).join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    File::create(&path).unwrap().write_all(text.as_bytes()).unwrap();
+    path
+}
+
+/// The `[global]` and `[devs]` sections of a plan lasting `weeks`, with
+/// each of `devs` working throughout.
+pub fn header(weeks: u32, devs: &[&str]) -> String {
+
+    let mut text = format!("[global]\n  - weeks: {}\n\n[devs]\n", weeks);
+    for dev in devs {
+        text.push_str(&format!("  - {}: 1..{}\n", dev, weeks));
+    }
+    text
+}
+
+/// Schedule a plan, and describe it.
+pub fn schedule(test: &str, text: &str) -> PlanReport {
+
+    let path = write_file(test, "plan.txt", text);
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let report = plan.schedule().unwrap().report().unwrap();
+    report
+}
+
+/// Schedule the tasks after a header for the devs, and describe the plan.
+pub fn schedule_tasks(test: &str, weeks: u32, devs: &[&str], tasks: &str) -> PlanReport {
+    schedule(test, &format!("{}\n{}", header(weeks, devs), tasks))
+}
+
+/// Find a node in the report by name, searching the whole tree.
+pub fn find_node<'a>(nodes: &'a [PlanNode], name: &str) -> &'a PlanNode {
+    find_node_in(nodes, name).unwrap_or_else(|| panic!("No node called \"{}\"", name))
+}
+
+fn find_node_in<'a>(nodes: &'a [PlanNode], name: &str) -> Option<&'a PlanNode> {
+
+    for node in nodes {
+        if node.name == name {
+            return Some(node);
+        }
+        if let Some(found) = find_node_in(&node.children, name) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// The first and last weeks in which a node has any resource.
+pub fn active_weeks(node: &PlanNode) -> (usize, usize) {
+
+    let first = node.weeks.iter().position(|w| *w > 0.0).expect("No resource allocated");
+    let last = node.weeks.iter().rposition(|w| *w > 0.0).unwrap();
+    (first, last)
+}
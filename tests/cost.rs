@@ -0,0 +1,92 @@
+// Costs, spend and forecasts.
+extern crate planner3;
+extern crate serde_json;
+
+mod common;
+
+use common::{header, schedule, write_file, find_node};
+use planner3::Plan;
+
+#[test]
+fn budget_heads_report_spend_and_forecast() {
+
+    let report = schedule("spend_forecast", "\
+[global]
+  - weeks: 4
+  - now: 2
+
+[devs]
+  - alice: 1..4
+  - alice-rate: 100
+
+Project
+  Build
+    - dev: alice
+    - resource: frontload
+    - plan: 10
+    - done: 1:5
+");
+
+    let project = find_node(&report.nodes, "Project");
+    assert_eq!(project.spent, 500.0);
+    assert_eq!(project.forecast, Some(1000.0));
+    assert_eq!(find_node(&report.nodes, "Build").forecast, None);
+}
+
+/// A project with the given budget, whose two tasks plan 14 days between
+/// them.
+fn budget_plan(budget: &str) -> String {
+    format!("{}
+Project
+  - budget: {}
+  - dev: alice
+  - resource: frontload
+  Build
+    - plan: 8
+  Test
+    - plan: 6
+", header(4, &["alice"]), budget)
+}
+
+/// Schedule the budget plan, and find the chart row for the project.
+fn project_row(test: &str, budget: &str) -> serde_json::Value {
+
+    let path = write_file(test, "plan.txt", &budget_plan(budget));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let context = plan.schedule().unwrap().chart("").unwrap();
+    let context = serde_json::to_value(&context).unwrap();
+    let row = context["rows"].as_array().unwrap().iter().find(|r| r["what"].as_str().unwrap().trim() == "Project").unwrap();
+    row.clone()
+}
+
+fn figure(row: &serde_json::Value, name: &str) -> String {
+    row[name].as_str().unwrap().replace("&nbsp;", "")
+}
+
+#[test]
+fn day_budget_is_compared_with_the_rolled_up_plan() {
+
+    let report = schedule("budget_rollup", &budget_plan("20"));
+    let project = find_node(&report.nodes, "Project");
+    assert_eq!(project.level, 1);
+    assert_eq!(project.plan, 14.0);
+    assert_eq!(project.budget, Some(20.0));
+}
+
+#[test]
+fn under_budget_shows_the_gain() {
+
+    let row = project_row("under_budget", "20");
+    assert_eq!(figure(&row, "plan"), "14");
+    assert_eq!(figure(&row, "budget"), "20");
+    assert_eq!(figure(&row, "budget_gain"), "6");
+    assert!(!row["notes"].as_array().unwrap().iter().any(|n| n.as_str().unwrap().starts_with("Over budget")));
+}
+
+#[test]
+fn over_budget_is_noted() {
+
+    let row = project_row("over_budget", "10");
+    assert_eq!(figure(&row, "budget_gain"), "-4");
+    assert!(row["notes"].as_array().unwrap().iter().any(|n| n.as_str() == Some("Over budget by 4")));
+}
@@ -0,0 +1,44 @@
+// Slack and the critical path.
+extern crate planner3;
+
+mod common;
+
+use common::{header, write_file, find_node};
+use planner3::Plan;
+
+#[test]
+fn chain_of_tasks_is_critical_and_task_with_float_is_not() {
+
+    let path = write_file("critical_chain", "plan.txt", &format!("{}
+Design
+  - dev: alice
+  - resource: frontload
+  - plan: 5
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 10
+  - after: Design
+Docs
+  - dev: bob
+  - resource: frontload
+  - plan: 2
+", header(4, &["alice", "bob"])));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let scheduled = plan.schedule().unwrap();
+
+    let report = scheduled.report().unwrap();
+    let design = find_node(&report.nodes, "Design");
+    let build = find_node(&report.nodes, "Build");
+    let docs = find_node(&report.nodes, "Docs");
+    assert_eq!((design.slack, design.critical), (Some(5.0), true));
+    assert_eq!((build.slack, build.critical), (Some(5.0), true));
+    assert_eq!((docs.slack, docs.critical), (Some(18.0), false));
+
+    let critical_path = scheduled.critical_path();
+    let path: Vec<&str> = critical_path.critical_path.iter().map(|t| t.path.as_str()).collect();
+    assert_eq!(path, vec!["Design", "Build"]);
+    assert_eq!(critical_path.project_end, Some("3/5/4".to_string()));
+    let docs = critical_path.tasks.iter().find(|t| t.path == "Docs").unwrap();
+    assert_eq!((docs.start.as_str(), docs.finish.as_str(), docs.slack), ("1/1/1", "1/2/4", 18.0));
+}
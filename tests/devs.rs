@@ -0,0 +1,133 @@
+// Reading the [devs] node.
+extern crate planner3;
+extern crate serde_json;
+
+mod common;
+
+use common::{schedule, write_file, find_node, active_weeks};
+use planner3::Plan;
+
+#[test]
+fn dev_names_may_contain_hyphens() {
+
+    let report = schedule("hyphenated_dev", "\
+[global]
+  - weeks: 3
+
+[devs]
+  - mary-jane: 1..3
+  - mary-jane-leave: 1
+
+Build
+  - dev: mary-jane
+  - resource: frontload
+  - plan: 5
+");
+
+    let build = find_node(&report.nodes, "Build");
+    assert_eq!(build.errors.len(), 0);
+    assert_eq!(active_weeks(build), (1, 1));
+}
+
+#[test]
+fn outsource_is_available_without_devs_node() {
+
+    let report = schedule("outsource_only", "\
+[global]
+  - weeks: 3
+
+Testing
+  - dev: outsource
+  - resource: frontload
+  - plan: 5
+");
+
+    assert_eq!(active_weeks(find_node(&report.nodes, "Testing")), (0, 0));
+}
+
+#[test]
+fn part_time_dev_is_not_over_allocated() {
+
+    let path = write_file("part_time_total", "plan.txt", "\
+[global]
+  - weeks: 2
+
+[devs]
+  - alice: 1..2
+  - alice-capacity: Mon-Wed
+
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 10
+");
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let context = plan.schedule().unwrap().dev_chart("alice", "").unwrap();
+    let context = serde_json::to_value(&context).unwrap();
+
+    let total = context["rows"].as_array().unwrap().iter().find(|r| r["what"].as_str() == Some("Total")).unwrap();
+    let days: Vec<String> = total["cells"].as_array().unwrap().iter()
+        .map(|c| c[1].as_str().unwrap().replace("&nbsp;", ""))
+        .collect();
+    assert_eq!(days, vec!["3", "3"]);
+    assert_eq!(total["notes"].as_array().unwrap().len(), 0);
+}
+
+/// Schedule more work for alice than they have time for, with the given
+/// capacity, and describe the plan.
+fn schedule_with_capacity(test: &str, capacity: &str) -> planner3::api::PlanReport {
+
+    schedule(test, &format!("\
+[global]
+  - weeks: 2
+
+[devs]
+  - alice: 1..2
+  - alice-capacity: {}
+
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 10
+", capacity))
+}
+
+#[test]
+fn capacity_percentage_is_whole_days() {
+
+    let report = schedule_with_capacity("capacity_percent", "60%");
+    assert_eq!(find_node(&report.nodes, "Build").weeks, vec![3.0, 3.0]);
+    assert_eq!(report.devs.iter().find(|d| d.name == "alice").unwrap().capacity, 60);
+}
+
+#[test]
+fn capacity_percentage_is_rounded_to_the_nearest_quarter() {
+
+    let report = schedule_with_capacity("capacity_rounded", "33%");
+    assert_eq!(find_node(&report.nodes, "Build").weeks, vec![1.75, 1.75]);
+    assert_eq!(report.devs.iter().find(|d| d.name == "alice").unwrap().capacity, 33);
+}
+
+#[test]
+fn capacity_days_are_worked_in_full() {
+
+    let report = schedule_with_capacity("capacity_days", "Mon-Wed");
+    assert_eq!(find_node(&report.nodes, "Build").weeks, vec![3.0, 3.0]);
+    assert_eq!(report.devs.iter().find(|d| d.name == "alice").unwrap().capacity, 60);
+}
+
+#[test]
+fn capacity_with_a_bad_day_is_reported() {
+
+    let report = schedule_with_capacity("capacity_bad_day", "Mon-Sun");
+    assert!(report.problems.iter().any(|p| p.contains("Cannot parse day of the week \"Sun\"")));
+    assert_eq!(find_node(&report.nodes, "Build").weeks, vec![5.0, 5.0]);
+}
+
+#[test]
+fn capacity_over_100_percent_is_reported() {
+
+    let report = schedule_with_capacity("capacity_over_100", "120%");
+    assert!(report.problems.iter().any(|p| p.contains("Capacity \"120%\" is more than 100%")));
+    assert_eq!(find_node(&report.nodes, "Build").weeks, vec![5.0, 5.0]);
+}
@@ -0,0 +1,126 @@
+// Writing a config back out with planner3 fmt.
+extern crate planner3;
+
+mod common;
+
+use common::write_file;
+use planner3::api::PlanNode;
+use planner3::Plan;
+
+const PLAN: &'static str = "\
+# Team plan
+[global]
+    - weeks: 4   # a month
+
+[devs]
+    - alice: 1..4
+
+Project    # the main work
+    - resource: frontload
+    - dev: alice
+    - note: |
+        First line
+
+          indented line
+    - note: \"Call #3 at noon\"
+    \"- Odd name\"
+        - plan: 1
+    Team
+        include: team.txt
+";
+
+const TEAM: &'static str = "\
+Build
+  - plan: 2
+";
+
+/// Describe each node, and how it was scheduled, without its location.
+fn describe(nodes: &[PlanNode], depth: usize, lines: &mut Vec<String>) {
+
+    for node in nodes {
+        lines.push(format!("{:width$}{} dev={:?} plan={} notes={:?} weeks={:?}",
+                           "",
+                           node.name,
+                           node.dev,
+                           node.plan,
+                           node.notes,
+                           node.weeks,
+                           width = depth * 2));
+        describe(&node.children, depth + 1, lines);
+    }
+}
+
+fn format_file(path: &str) -> String {
+    Plan::load(path).unwrap().format().unwrap()
+}
+
+fn describe_file(path: &str) -> Vec<String> {
+
+    let plan = Plan::load(path).unwrap();
+    let report = plan.schedule().unwrap().report().unwrap();
+    let mut lines = Vec::new();
+    describe(&report.nodes, 0, &mut lines);
+    lines
+}
+
+#[test]
+fn formatting_twice_changes_nothing_more() {
+
+    write_file("format_twice", "team.txt", TEAM);
+    let path = write_file("format_twice", "plan.txt", PLAN);
+    let once = format_file(path.to_str().unwrap());
+
+    let path = write_file("format_twice", "once.txt", &once);
+    let twice = format_file(path.to_str().unwrap());
+
+    assert_eq!(twice, once);
+    assert!(once.contains("# Team plan\n"));
+    assert!(once.contains("    include: team.txt\n"));
+    assert!(once.contains("  - note: \"Call #3 at noon\"\n"));
+    assert!(once.contains("  \"- Odd name\"\n"));
+}
+
+#[test]
+fn formatted_plan_reads_the_same() {
+
+    write_file("format_same", "team.txt", TEAM);
+    let path = write_file("format_same", "plan.txt", PLAN);
+    let before = describe_file(path.to_str().unwrap());
+
+    let path = write_file("format_same", "formatted.txt", &format_file(path.to_str().unwrap()));
+    let after = describe_file(path.to_str().unwrap());
+
+    assert_eq!(after, before);
+    assert!(before.iter().any(|l| l.starts_with("    Build ")));
+}
+
+#[test]
+fn indent_warnings_do_not_stop_formatting() {
+
+    let path = write_file("format_warnings", "plan.txt", "\
+[global]
+  - weeks: 4
+
+Project
+\t- plan: 1
+");
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+
+    assert_eq!(plan.warnings().len(), 1);
+    assert!(plan.format().unwrap().contains("Project\n  - plan: 1\n"));
+}
+
+#[test]
+fn parse_errors_stop_formatting() {
+
+    let path = write_file("format_errors", "plan.txt", "\
+[global]
+  - weeks: 4
+
+Project
+  - note: \"Unfinished
+");
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+
+    assert!(plan.format().is_err());
+}
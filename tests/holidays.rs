@@ -0,0 +1,55 @@
+// Public holidays and leave.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule, find_node, active_weeks};
+
+#[test]
+fn holidays_apply_to_devs_listed_after_them() {
+
+    let report = schedule("holidays_before_devs", "\
+[global]
+  - weeks: 3
+
+[holidays]
+  - shutdown: 1
+
+[devs]
+  - alice: 1..3
+
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 5
+");
+
+    assert_eq!(active_weeks(find_node(&report.nodes, "Build")), (1, 1));
+}
+
+#[test]
+fn contractors_work_through_holidays() {
+
+    let report = schedule("contractor_holidays", "\
+[global]
+  - weeks: 3
+
+[devs]
+  - alice: 1..3
+
+[holidays]
+  - shutdown: 1
+
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 5
+Testing
+  - dev: outsource
+  - resource: frontload
+  - plan: 5
+");
+
+    assert_eq!(active_weeks(find_node(&report.nodes, "Build")), (1, 1));
+    assert_eq!(active_weeks(find_node(&report.nodes, "Testing")), (0, 0));
+}
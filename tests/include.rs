@@ -0,0 +1,45 @@
+// Splitting a plan across files.
+extern crate planner3;
+
+mod common;
+
+use common::{header, write_file};
+use planner3::Plan;
+
+/// A plan whose project includes the given file.
+fn plan_text(include: &str) -> String {
+    format!("{}
+Project
+  - resource: frontload
+  - dev: alice
+  include: {}
+", header(4, &["alice"]), include)
+}
+
+#[test]
+fn problems_name_the_included_file() {
+
+    write_file("include_problem", "teams/build.txt", "\
+Build
+  - plan: 3
+  - after: Nowhere
+");
+    let path = write_file("include_problem", "plan.txt", &plan_text("teams/build.txt"));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let report = plan.schedule().unwrap().report().unwrap();
+
+    assert_eq!(report.problems.len(), 1);
+    assert!(report.problems[0].contains("teams/build.txt:1: Build: "));
+}
+
+#[test]
+fn includes_outside_the_plan_directory_are_refused() {
+
+    write_file("include_outside", "secret.txt", "Build\n  - plan: 3\n");
+    let path = write_file("include_outside", "plans/plan.txt", &plan_text("../secret.txt"));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let report = plan.schedule().unwrap().report().unwrap();
+
+    assert_eq!(report.nodes[0].children.len(), 0);
+    assert!(report.problems[0].contains("outside the directory of the plan"));
+}
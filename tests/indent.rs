@@ -0,0 +1,57 @@
+// Indentation with tabs, and lines between two levels.
+extern crate planner3;
+
+mod common;
+
+use common::{header, schedule_tasks, write_file, find_node};
+use planner3::Plan;
+
+#[test]
+fn tabs_after_spaces_count_as_four_columns() {
+
+    let path = write_file("indent_tabs", "plan.txt", &format!("{}
+Project
+\t- dev: alice
+\t- resource: frontload
+\tBuild
+\t\t- plan: 1
+    Test
+        - plan: 2
+", header(4, &["alice"])));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let report = plan.schedule().unwrap().report().unwrap();
+
+    let project = find_node(&report.nodes, "Project");
+    let children: Vec<&str> = project.children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(children, vec!["Build", "Test"]);
+    assert_eq!(find_node(&report.nodes, "Test").plan, 2.0);
+
+    let warnings = plan.warnings();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains("plan.txt:8:1: Indent uses tabs, but earlier lines use spaces; a tab counts as 4 columns"));
+    assert!(warnings[1].contains("plan.txt:12:1: Indent uses spaces, but earlier lines use tabs"));
+    assert_eq!(report.problems.len(), 2);
+}
+
+#[test]
+fn dedent_between_levels_is_flagged() {
+
+    let report = schedule_tasks("indent_between", 4, &["alice"], "\
+Project
+  - dev: alice
+  - resource: frontload
+    Build
+      - plan: 1
+  Test
+    - plan: 2
+");
+
+    let project = find_node(&report.nodes, "Project");
+    let children: Vec<&str> = project.children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(children, vec!["Build", "Test"]);
+
+    let test = find_node(&report.nodes, "Test");
+    assert_eq!(test.errors.len(), 1);
+    assert!(test.errors[0].contains("plan.txt:12:3: Indent doesn't match any enclosing level; \
+                                     read as part of \"Project\", which may not be what was meant"));
+}
@@ -0,0 +1,32 @@
+// Node names with any printable text.
+extern crate planner3;
+extern crate serde_json;
+
+mod common;
+
+use common::{header, write_file, find_node};
+use planner3::Plan;
+
+#[test]
+fn unicode_names_are_read_and_escaped_in_html() {
+
+    let path = write_file("unicode_names", "plan.txt", &format!("{}
+\"Café <b> & \\\"Co\\\"\"
+  - dev: zoë
+  - resource: frontload
+  Ship it: v2+ 🚀
+    - plan: 1
+", header(2, &["zoë"])));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let scheduled = plan.schedule().unwrap();
+
+    let report = scheduled.report().unwrap();
+    assert_eq!(report.problems.len(), 0);
+    assert_eq!(report.nodes[0].name, "Café <b> & \"Co\"");
+    assert_eq!(find_node(&report.nodes, "Ship it: v2+ 🚀").dev, Some("zoë".to_string()));
+
+    let context = serde_json::to_value(&scheduled.chart("").unwrap()).unwrap();
+    let names: Vec<&str> = context["rows"].as_array().unwrap().iter().map(|r| r["what_html"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["&nbsp;&nbsp;&nbsp;Café&nbsp;&lt;b&gt;&nbsp;&amp;&nbsp;&quot;Co&quot;",
+                           "&nbsp;&nbsp;&nbsp;&nbsp;&nbsp;&nbsp;Ship&nbsp;it:&nbsp;v2+&nbsp;🚀"]);
+}
@@ -0,0 +1,43 @@
+// Notes, with quoted and block values.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule_tasks, find_node};
+
+#[test]
+fn block_ends_before_a_deeper_child() {
+
+    let report = schedule_tasks("block_child", 4, &["alice"], "\
+Project
+  - dev: alice
+  - resource: frontload
+  - note: |
+      First line
+        indented line
+    Child
+      - plan: 2
+");
+
+    let project = find_node(&report.nodes, "Project");
+    assert_eq!(project.notes, vec!["First line\n  indented line".to_string()]);
+    assert_eq!(project.children.len(), 1);
+    assert_eq!(find_node(&report.nodes, "Child").plan, 2.0);
+}
+
+#[test]
+fn value_starting_with_a_quote_is_kept_as_written() {
+
+    let report = schedule_tasks("partly_quoted", 4, &["alice"], "\
+Task
+  - dev: alice
+  - resource: frontload
+  - plan: 2
+  - note: \"Phase 2\" slipped
+  - note: \"All # quoted\"
+");
+
+    let task = find_node(&report.nodes, "Task");
+    assert_eq!(task.errors.len(), 0);
+    assert_eq!(task.notes, vec!["\"Phase 2\" slipped".to_string(), "All # quoted".to_string()]);
+}
@@ -0,0 +1,71 @@
+// Assignment of pooled tasks to devs.
+extern crate planner3;
+
+mod common;
+
+use common::{header, schedule, find_node};
+use planner3::api::PlanReport;
+
+/// Schedule the tasks with two devs: alice, who is free throughout, and bob,
+/// who is only free in week 2.
+fn schedule_pool(test: &str, tasks: &str) -> PlanReport {
+    schedule(test, &format!("{}  - bob-leave: 1, 3..4\n\n{}", header(4, &["alice", "bob"]), tasks))
+}
+
+#[test]
+fn frontloaded_pool_task_goes_to_first_free_dev() {
+
+    let report = schedule_pool("pool_frontload", "\
+Task
+  - pool: any
+  - resource: frontload
+  - plan: 5
+");
+
+    assert_eq!(find_node(&report.nodes, "Task").dev, Some("alice".to_string()));
+}
+
+#[test]
+fn backloaded_pool_task_goes_to_dev_finishing_first() {
+
+    let report = schedule_pool("pool_backload", "\
+Task
+  - pool: any
+  - resource: backload
+  - plan: 5
+");
+
+    assert_eq!(find_node(&report.nodes, "Task").dev, Some("bob".to_string()));
+}
+
+#[test]
+fn dev_any_is_the_pool_of_every_dev() {
+
+    let report = schedule_pool("pool_dev_any", "\
+Task
+  - dev: any
+  - resource: backload
+  - plan: 5
+");
+
+    let task = find_node(&report.nodes, "Task");
+    assert_eq!(task.errors.len(), 0);
+    assert_eq!(task.dev, Some("bob".to_string()));
+}
+
+#[test]
+fn empty_pool_is_reported_once() {
+
+    let report = schedule_pool("pool_empty", "\
+Project
+  - resource: prodsfr
+  Task
+    - pool: ops
+    - plan: 5
+");
+
+    assert_eq!(find_node(&report.nodes, "Project").errors.len(), 0);
+    assert_eq!(find_node(&report.nodes, "Task").errors, vec!["No devs in pool \"ops\"".to_string()]);
+    assert_eq!(report.problems.len(), 1);
+    assert!(report.problems[0].ends_with("plan.txt:11: Task: No devs in pool \"ops\""));
+}
@@ -0,0 +1,63 @@
+// Reporting problems in the config.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule, schedule_tasks, write_file};
+use planner3::errors;
+use planner3::Plan;
+
+#[test]
+fn bad_global_settings_do_not_stop_the_plan() {
+
+    let report = schedule("bad_global", "\
+[global]
+  - weeks: 4
+  - now: soon
+  - label: garbage
+
+[devs]
+  - alice: 1..4
+");
+
+    assert_eq!(report.weeks, 4);
+    assert_eq!(report.problems.len(), 2);
+    assert!(report.problems[0].contains("plan.txt:3:5: Failed to read \"now\""));
+}
+
+#[test]
+fn problems_give_their_location_once() {
+
+    let report = schedule_tasks("located_problem", 4, &["alice"], "\
+Task
+  - dev: alice
+  - resource: frontload
+  - budget: lots
+  - plan: 3
+");
+
+    assert_eq!(report.problems.len(), 1);
+    assert!(report.problems[0].contains("plan.txt:10:5: Failed to add attribute \"budget\""));
+}
+
+#[test]
+fn fatal_error_points_at_the_line_with_a_caret() {
+
+    let path = write_file("caret", "plan.txt", "\
+[global]
+  - weeks: lots
+
+Task
+  - plan: 1
+");
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let error = match plan.schedule() {
+        Ok(_) => panic!("A plan without a length was scheduled"),
+        Err(e) => errors::render_error(&e),
+    };
+
+    let lines: Vec<&str> = error.lines().collect();
+    assert!(lines[0].starts_with("error: Failed to read \"weeks\""));
+    assert!(lines[1].starts_with(" --> ") && lines[1].ends_with("plan.txt:2:5"));
+    assert_eq!(lines[2..].to_vec(), vec!["  |", "2 |   - weeks: lots", "  |     ^^^^^^^^^^^"]);
+}
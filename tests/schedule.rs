@@ -0,0 +1,116 @@
+// Ordering of resource allocation between dependent tasks.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule_tasks, header, write_file, find_node, active_weeks};
+use planner3::errors;
+use planner3::Plan;
+
+#[test]
+fn smeared_successor_waits_for_frontloaded_predecessor() {
+
+    let report = schedule_tasks("smeared_successor", 4, &["alice", "bob"], "\
+Design
+  - dev: alice
+  - resource: frontload
+  - plan: 5
+Build
+  - dev: bob
+  - resource: smearremaining
+  - plan: 5
+  - after: Design
+");
+
+    let design = find_node(&report.nodes, "Design");
+    let build = find_node(&report.nodes, "Build");
+    assert_eq!(active_weeks(design), (0, 0));
+    assert!(active_weeks(build).0 > active_weeks(design).1);
+}
+
+#[test]
+fn backloaded_successor_waits_for_frontloaded_predecessor() {
+
+    let report = schedule_tasks("backloaded_successor", 4, &["alice", "bob"], "\
+Design
+  - dev: alice
+  - resource: frontload
+  - plan: 10
+Build
+  - dev: bob
+  - resource: backload
+  - plan: 10
+  - after: Design
+");
+
+    let design = find_node(&report.nodes, "Design");
+    let build = find_node(&report.nodes, "Build");
+    assert!(active_weeks(build).0 > active_weeks(design).1);
+}
+
+#[test]
+fn serial_siblings_with_mixed_resourcing_run_in_order() {
+
+    let report = schedule_tasks("serial_mixed", 4, &["alice", "bob"], "\
+Project
+  - schedule: serial
+  First
+    - dev: alice
+    - resource: frontload
+    - plan: 5
+  Second
+    - dev: bob
+    - resource: smearremaining
+    - plan: 5
+  Third
+    - dev: alice
+    - resource: backload
+    - plan: 2
+");
+
+    let first = find_node(&report.nodes, "First");
+    let second = find_node(&report.nodes, "Second");
+    let third = find_node(&report.nodes, "Third");
+    assert!(active_weeks(second).0 > active_weeks(first).1);
+    assert!(active_weeks(third).0 >= active_weeks(second).1);
+}
+
+#[test]
+fn without_dependencies_smeared_work_is_allocated_first() {
+
+    let report = schedule_tasks("no_dependencies", 4, &["alice"], "\
+Early
+  - dev: alice
+  - resource: frontload
+  - plan: 10
+Steady
+  - dev: alice
+  - resource: smearremaining
+  - plan: 8
+");
+
+    assert_eq!(find_node(&report.nodes, "Steady").weeks, vec![2.0, 2.0, 2.0, 2.0]);
+    assert_eq!(find_node(&report.nodes, "Early").weeks, vec![3.0, 3.0, 3.0, 1.0]);
+}
+
+#[test]
+fn dependency_cycle_is_reported_with_lines() {
+
+    let path = write_file("dependency_cycle", "plan.txt", &format!("{}
+Design
+  - dev: alice
+  - plan: 2
+  - after: Build
+Build
+  - dev: alice
+  - plan: 2
+  - after: Design
+", header(4, &["alice"])));
+    let plan = Plan::load(path.to_str().unwrap()).unwrap();
+    let error = match plan.schedule() {
+        Ok(_) => panic!("A cycle of dependencies was scheduled"),
+        Err(e) => errors::render_error(&e),
+    };
+
+    assert!(error.contains("Dependency cycle between tasks at lines 7 -> 11 -> 7"));
+}
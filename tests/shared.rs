@@ -0,0 +1,72 @@
+// Tasks shared between several devs.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule, find_node};
+
+#[test]
+fn yearly_plan_covers_time_when_any_dev_is_present() {
+
+    let report = schedule("shared_pcy", "\
+[global]
+  - weeks: 52
+
+[devs]
+  - alice: 1..13
+  - bob: 14..26
+
+Support
+  - dev: alice, bob
+  - resource: smearremaining
+  - plan: 20pcy
+");
+
+    assert_eq!(find_node(&report.nodes, "Support").plan, 10.0);
+}
+
+#[test]
+fn done_is_recorded_against_the_dev_who_did_it() {
+
+    let report = schedule("shared_done", "\
+[global]
+  - weeks: 4
+  - now: 3
+
+[devs]
+  - alice: 1..4
+  - bob: 1..4
+
+Build
+  - dev: alice, bob
+  - plan: 10
+  - done: alice 1:4
+");
+
+    let build = find_node(&report.nodes, "Build");
+    let alice = build.dev_split.iter().find(|s| s.name == "alice").unwrap();
+    assert_eq!(alice.weeks[0], 4.0);
+    let bob = build.dev_split.iter().find(|s| s.name == "bob").unwrap();
+    assert_eq!(bob.weeks[0], 0.0);
+}
+
+#[test]
+fn done_on_a_shared_task_must_name_the_dev() {
+
+    let report = schedule("shared_done_unnamed", "\
+[global]
+  - weeks: 4
+  - now: 3
+
+[devs]
+  - alice: 1..4
+  - bob: 1..4
+
+Build
+  - dev: alice, bob
+  - plan: 10
+  - done: 1:4
+");
+
+    assert!(find_node(&report.nodes, "Build").errors.len() != 0);
+}
@@ -0,0 +1,70 @@
+// Checks on the plan before any resource is allocated.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule, find_node};
+
+#[test]
+fn every_broken_invariant_is_reported_with_its_line() {
+
+    let report = schedule("validate_all", "\
+[global]
+  - weeks: 4
+
+[devs]
+  - alice: 1..4
+  - bob: 3..4
+
+Project
+  - resource: frontload
+  - plan: 5
+  - done: 1:1
+  Build
+    - dev: carol
+    - plan: 2
+  Test
+    - dev: bob
+    - plan: 2
+    - done: 1:1
+  Docs
+    - dev: alice
+    - earliest-start: 3
+    - latest-end: 2
+");
+
+    let expected = vec!["plan.txt:8: Project: \"done\" given on a node with children; record it on the tasks",
+                        "plan.txt:8: Project: \"plan\" given on a node with children; use \"default-plan\" instead",
+                        "plan.txt:13:7: Failed to add attribute \"dev\": Failed to set dev: Developer \"carol\" not known",
+                        "plan.txt:12: Build: No dev for this task",
+                        "plan.txt:15: Test: done at 1 is outside the time range for bob",
+                        "plan.txt:19: Docs: latest-end is before earliest-start",
+                        "plan.txt:19: Docs: No plan for this task"];
+    for problem in expected {
+        assert!(report.problems.iter().any(|p| p.contains(problem)), "\"{}\" not reported", problem);
+    }
+}
+
+#[test]
+fn shared_done_work_is_checked_against_its_own_dev() {
+
+    let report = schedule("validate_shared_done", "\
+[global]
+  - weeks: 4
+  - now: 2
+
+[devs]
+  - alice: 1..4
+  - bob: 3..4
+
+Pair
+  - dev: alice, bob
+  - resource: frontload
+  - plan: 10
+  - done: alice 1:2
+");
+
+    let pair = find_node(&report.nodes, "Pair");
+    assert_eq!(pair.errors, Vec::<String>::new());
+    assert_eq!(pair.done, 2.0);
+}
@@ -0,0 +1,95 @@
+// Allocating within earliest-start and latest-end.
+extern crate planner3;
+
+mod common;
+
+use common::{schedule, find_node, active_weeks};
+
+#[test]
+fn backload_fills_up_to_latest_end() {
+
+    let report = schedule("backload_latest_end", "\
+[global]
+  - weeks: 4
+
+[devs]
+  - alice: 1..4
+
+Build
+  - dev: alice
+  - resource: backload
+  - plan: 5
+  - latest-end: 2
+");
+
+    let build = find_node(&report.nodes, "Build");
+    assert_eq!(build.errors.len(), 0);
+    assert_eq!(build.weeks, vec![0.0, 5.0, 0.0, 0.0]);
+}
+
+#[test]
+fn backload_from_the_first_cell_reports_what_does_not_fit() {
+
+    let report = schedule("backload_first_cell", "\
+[global]
+  - weeks: 1
+
+[devs]
+  - alice: 1..1
+
+Build
+  - dev: alice
+  - resource: backload
+  - plan: 8
+");
+
+    let build = find_node(&report.nodes, "Build");
+    assert_eq!(build.weeks, vec![5.0]);
+    assert_eq!(build.errors.len(), 1);
+    assert!(build.errors[0].contains("3 days unallocated"));
+}
+
+#[test]
+fn frontload_waits_for_earliest_start() {
+
+    let report = schedule("frontload_earliest_start", "\
+[global]
+  - weeks: 12
+
+[devs]
+  - alice: 1..12
+
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 5
+  - earliest-start: 10
+");
+
+    let build = find_node(&report.nodes, "Build");
+    assert_eq!(build.errors.len(), 0);
+    assert_eq!(active_weeks(build), (9, 9));
+}
+
+#[test]
+fn plan_overflowing_latest_end_is_noted() {
+
+    let report = schedule("overflow_latest_end", "\
+[global]
+  - weeks: 4
+
+[devs]
+  - alice: 1..4
+
+Build
+  - dev: alice
+  - resource: frontload
+  - plan: 8
+  - latest-end: 1
+");
+
+    let build = find_node(&report.nodes, "Build");
+    assert_eq!(build.weeks, vec![5.0, 0.0, 0.0, 0.0]);
+    assert_eq!(build.errors.len(), 1);
+    assert!(build.errors[0].contains("3 days unallocated; plan does not fit within 1/1/1..1/5/4"));
+}